@@ -0,0 +1,144 @@
+//! Compares the sorted holiday index with the previous implementation, which
+//! walked every day of a range and probed a `HashMap<String, String>`.
+//!
+//! Run with `cargo bench --bench queries`.
+
+use assert_fs::prelude::*;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use holidays_jp::cache::{CacheData, CacheMetadata};
+use holidays_jp::{rules, Config, HolidayService};
+use std::collections::HashMap;
+
+const FIRST_YEAR: i32 = 1955;
+const LAST_YEAR: i32 = 2024;
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn holiday_names() -> HashMap<NaiveDate, String> {
+    (FIRST_YEAR..=LAST_YEAR)
+        .flat_map(rules::holidays_in_year)
+        .map(|holiday| (holiday.date, holiday.name))
+        .collect()
+}
+
+/// A service initialized from a cache file, without network access
+fn indexed_service(dir: &assert_fs::TempDir) -> HolidayService {
+    let cache_file = dir.child("holidays.json");
+    let cache = CacheData {
+        metadata: CacheMetadata {
+            last_updated: Utc::now(),
+            etag: None,
+            last_etag_check: None,
+            last_modified: None,
+        },
+        holidays: holiday_names(),
+    };
+    cache_file
+        .write_str(&serde_json::to_string(&cache).unwrap())
+        .unwrap();
+
+    let mut config = Config::default();
+    config.holiday_data.cache_file = cache_file.path().to_string_lossy().to_string();
+
+    let mut service = HolidayService::new(config);
+    service.initialize_from_cache().unwrap();
+    service
+}
+
+/// The data layout and range walk used before the index
+struct Legacy {
+    holidays: HashMap<String, String>,
+}
+
+impl Legacy {
+    fn new() -> Self {
+        Self {
+            holidays: holiday_names()
+                .into_iter()
+                .map(|(date, name)| (date.format("%Y-%m-%d").to_string(), name))
+                .collect(),
+        }
+    }
+
+    fn range(&self, start: NaiveDate, end: NaiveDate) -> Vec<(String, String)> {
+        let mut result = Vec::new();
+        let mut current = start;
+        while current <= end {
+            let date_str = current.format("%Y-%m-%d").to_string();
+            if let Some(name) = self.holidays.get(&date_str) {
+                result.push((date_str, name.clone()));
+            }
+            current += Duration::days(1);
+        }
+        result
+    }
+
+    fn next(&self, from: NaiveDate) -> Option<(String, String)> {
+        let mut current = from + Duration::days(1);
+        while current.year() <= LAST_YEAR {
+            let date_str = current.format("%Y-%m-%d").to_string();
+            if let Some(name) = self.holidays.get(&date_str) {
+                return Some((date_str, name.clone()));
+            }
+            current += Duration::days(1);
+        }
+        None
+    }
+}
+
+fn range_queries(c: &mut Criterion) {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let service = indexed_service(&dir);
+    let legacy = Legacy::new();
+    let (start, end) = (date(FIRST_YEAR, 1, 1), date(LAST_YEAR, 12, 31));
+
+    let mut group = c.benchmark_group("range_70_years");
+    group.bench_function("legacy", |b| {
+        b.iter(|| legacy.range(black_box(start), black_box(end)))
+    });
+    group.bench_function("index", |b| {
+        b.iter(|| service.holidays_between(black_box(start), black_box(end)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("count_70_years");
+    group.bench_function("legacy", |b| {
+        b.iter(|| legacy.range(black_box(start), black_box(end)).len())
+    });
+    group.bench_function("index", |b| {
+        b.iter(|| service.count_holidays(black_box(start), black_box(end)))
+    });
+    group.finish();
+}
+
+fn point_queries(c: &mut Criterion) {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let service = indexed_service(&dir);
+    let legacy = Legacy::new();
+    let from = date(2024, 5, 7);
+
+    let mut group = c.benchmark_group("next_holiday");
+    group.bench_function("legacy", |b| b.iter(|| legacy.next(black_box(from))));
+    group.bench_function("index", |b| {
+        b.iter(|| service.next_holiday(black_box(from)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("lookup");
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            legacy
+                .holidays
+                .get(&black_box(from).format("%Y-%m-%d").to_string())
+                .cloned()
+        })
+    });
+    group.bench_function("index", |b| b.iter(|| service.lookup(black_box(from))));
+    group.finish();
+}
+
+criterion_group!(benches, range_queries, point_queries);
+criterion_main!(benches);
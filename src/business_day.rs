@@ -0,0 +1,251 @@
+//! Working-calendar definitions used by business-day arithmetic.
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The set of weekdays that are never business days
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<Weekday>", into = "Vec<Weekday>")]
+pub struct Weekend {
+    /// One bit per weekday, indexed by `Weekday::num_days_from_monday`
+    mask: u8,
+}
+
+impl Weekend {
+    pub fn new(days: &[Weekday]) -> Self {
+        Self {
+            mask: days
+                .iter()
+                .fold(0, |mask, day| mask | 1 << day.num_days_from_monday()),
+        }
+    }
+
+    /// A calendar where every weekday is a working day
+    pub fn none() -> Self {
+        Self { mask: 0 }
+    }
+
+    pub fn contains(&self, day: Weekday) -> bool {
+        self.mask & (1 << day.num_days_from_monday()) != 0
+    }
+
+    pub fn days(&self) -> Vec<Weekday> {
+        ALL_WEEKDAYS
+            .iter()
+            .copied()
+            .filter(|day| self.contains(*day))
+            .collect()
+    }
+
+    /// True when no weekday is left as a working day
+    pub fn is_whole_week(&self) -> bool {
+        self.mask == 0b111_1111
+    }
+}
+
+impl Default for Weekend {
+    /// Saturday and Sunday
+    fn default() -> Self {
+        Self::new(&[Weekday::Sat, Weekday::Sun])
+    }
+}
+
+impl From<Vec<Weekday>> for Weekend {
+    fn from(days: Vec<Weekday>) -> Self {
+        Self::new(&days)
+    }
+}
+
+impl From<Weekend> for Vec<Weekday> {
+    fn from(weekend: Weekend) -> Self {
+        weekend.days()
+    }
+}
+
+/// A period on which the office is closed, in addition to weekends and holidays
+///
+/// Written in `config.toml` as `"MM-DD"` or `"YYYY-MM-DD"`, optionally as a range
+/// joined with `..`. Annual ranges may wrap around the year end, e.g. `"12-29..01-03"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ClosedPeriod {
+    /// Every year between two (month, day) pairs, inclusive
+    Annual { from: (u32, u32), to: (u32, u32) },
+    /// Between two dates, inclusive
+    Dates { from: NaiveDate, to: NaiveDate },
+}
+
+impl ClosedPeriod {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        match *self {
+            ClosedPeriod::Annual { from, to } => {
+                let month_day = (date.month(), date.day());
+                if from <= to {
+                    from <= month_day && month_day <= to
+                } else {
+                    month_day >= from || month_day <= to
+                }
+            }
+            ClosedPeriod::Dates { from, to } => from <= date && date <= to,
+        }
+    }
+}
+
+enum PeriodBound {
+    MonthDay((u32, u32)),
+    Date(NaiveDate),
+}
+
+fn parse_period_bound(s: &str) -> Result<PeriodBound, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(PeriodBound::Date(date));
+    }
+    // A leap year accepts 02-29
+    NaiveDate::parse_from_str(&format!("2000-{}", s), "%Y-%m-%d")
+        .map(|date| PeriodBound::MonthDay((date.month(), date.day())))
+        .map_err(|_| format!("Invalid closed day '{}': expected MM-DD or YYYY-MM-DD", s))
+}
+
+impl FromStr for ClosedPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s.split_once("..").unwrap_or((s, s));
+        match (
+            parse_period_bound(from.trim())?,
+            parse_period_bound(to.trim())?,
+        ) {
+            (PeriodBound::MonthDay(from), PeriodBound::MonthDay(to)) => {
+                Ok(ClosedPeriod::Annual { from, to })
+            }
+            (PeriodBound::Date(from), PeriodBound::Date(to)) if from <= to => {
+                Ok(ClosedPeriod::Dates { from, to })
+            }
+            (PeriodBound::Date(_), PeriodBound::Date(_)) => Err(format!(
+                "Invalid closed period '{}': start must not be after end",
+                s
+            )),
+            _ => Err(format!(
+                "Invalid closed period '{}': cannot mix MM-DD and YYYY-MM-DD",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for ClosedPeriod {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for ClosedPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ClosedPeriod::Annual { from, to } if from == to => {
+                write!(f, "{:02}-{:02}", from.0, from.1)
+            }
+            ClosedPeriod::Annual { from, to } => {
+                write!(f, "{:02}-{:02}..{:02}-{:02}", from.0, from.1, to.0, to.1)
+            }
+            ClosedPeriod::Dates { from, to } if from == to => write!(f, "{}", from),
+            ClosedPeriod::Dates { from, to } => write!(f, "{}..{}", from, to),
+        }
+    }
+}
+
+impl From<ClosedPeriod> for String {
+    fn from(period: ClosedPeriod) -> Self {
+        period.to_string()
+    }
+}
+
+/// An office calendar layered on top of the national holidays
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkCalendar {
+    pub weekend: Weekend,
+    /// Extra closed days, e.g. `"12-29..01-03"` for 年末年始
+    pub closed_days: Vec<ClosedPeriod>,
+    /// Days that are working days even if they fall on a weekend, holiday or closed day
+    pub working_days: Vec<NaiveDate>,
+}
+
+impl WorkCalendar {
+    pub fn is_working_day_override(&self, date: NaiveDate) -> bool {
+        self.working_days.contains(&date)
+    }
+
+    /// True when `date` is a weekend day or an extra closed day; holidays are not considered
+    pub fn is_closed(&self, date: NaiveDate) -> bool {
+        self.weekend.contains(date.weekday())
+            || self.closed_days.iter().any(|period| period.contains(date))
+    }
+}
+
+const ALL_WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekend() {
+        let weekend = Weekend::default();
+        assert!(weekend.contains(Weekday::Sat));
+        assert!(weekend.contains(Weekday::Sun));
+        assert!(!weekend.contains(Weekday::Mon));
+        assert_eq!(weekend.days(), vec![Weekday::Sat, Weekday::Sun]);
+
+        assert!(!Weekend::none().contains(Weekday::Sun));
+        assert!(Weekend::new(&ALL_WEEKDAYS).is_whole_week());
+    }
+
+    #[test]
+    fn test_closed_period_parsing() {
+        let year_end: ClosedPeriod = "12-29..01-03".parse().unwrap();
+        assert!(year_end.contains(NaiveDate::from_ymd_opt(2023, 12, 30).unwrap()));
+        assert!(year_end.contains(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap()));
+        assert!(!year_end.contains(NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()));
+        assert_eq!(year_end.to_string(), "12-29..01-03");
+
+        let single: ClosedPeriod = "2024-08-13".parse().unwrap();
+        assert!(single.contains(NaiveDate::from_ymd_opt(2024, 8, 13).unwrap()));
+        assert!(!single.contains(NaiveDate::from_ymd_opt(2025, 8, 13).unwrap()));
+
+        assert!("02-30".parse::<ClosedPeriod>().is_err());
+        assert!("12-29..2024-01-03".parse::<ClosedPeriod>().is_err());
+    }
+
+    #[test]
+    fn test_work_calendar_from_toml() {
+        let calendar: WorkCalendar = toml::from_str(
+            r#"
+            weekend = ["Sun"]
+            closed_days = ["12-29..01-03"]
+            working_days = ["2023-12-30"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(calendar.weekend.days(), vec![Weekday::Sun]);
+        assert!(calendar.is_closed(NaiveDate::from_ymd_opt(2023, 12, 29).unwrap()));
+        assert!(calendar.is_working_day_override(NaiveDate::from_ymd_opt(2023, 12, 30).unwrap()));
+        assert_eq!(
+            toml::from_str::<WorkCalendar>("").unwrap(),
+            WorkCalendar::default()
+        );
+    }
+}
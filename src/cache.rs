@@ -1,79 +1,255 @@
-use crate::config::{CacheStrategy, Config};
-use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use reqwest;
+use crate::config::{CacheStrategy, Config, RefreshErrorPolicy};
+use crate::diff::HolidayDiff;
+use crate::holiday::{build_holidays, Holiday};
+use crate::parse::{self, ParseReport};
+use crate::sanity;
+use crate::source::{self, HolidaySource, SourceData, SourceMetadata};
+use crate::store::{self, CacheStore, CorruptCache};
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheMetadata {
     pub last_updated: DateTime<Utc>,
     pub etag: Option<String>,
     pub last_etag_check: Option<DateTime<Utc>>,
+    /// `Last-Modified` of the downloaded data, sent back as `If-Modified-Since`
+    #[serde(default)]
+    pub last_modified: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl CacheMetadata {
+    /// The validators for a conditional fetch of newer data
+    pub fn validators(&self) -> SourceMetadata {
+        SourceMetadata {
+            etag: self.etag.clone(),
+            last_modified: self.last_modified,
+        }
+    }
+
+    fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheData {
     pub metadata: CacheMetadata,
-    pub holidays: HashMap<String, String>,
+    pub holidays: HashMap<NaiveDate, String>,
 }
 
+/// Why served data may be out of date: a refresh was due but failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Staleness {
+    /// When the served data was downloaded
+    pub last_updated: DateTime<Utc>,
+    /// The refresh error
+    pub error: String,
+}
+
+/// Holidays loaded through the cache
+#[derive(Debug, Clone)]
+pub struct CachedHolidays {
+    pub holidays: HashMap<NaiveDate, Holiday>,
+    /// Set when the existing cache was served because the refresh failed
+    pub stale: Option<Staleness>,
+    /// How the downloaded CSV was parsed; `None` when the data came from the cache
+    pub report: Option<ParseReport>,
+    /// What the download changed in the cache; `None` when the data came from the cache
+    pub diff: Option<HolidayDiff>,
+    /// Set when the cache could not be parsed and was replaced, with the reason
+    pub discarded: Option<String>,
+}
+
+#[derive(Clone)]
 pub struct HolidayCache {
     config: Config,
-    cache_path: PathBuf,
-    http_client: reqwest::Client,
+    store: Arc<dyn CacheStore>,
+    source: Arc<dyn HolidaySource>,
 }
 
 impl HolidayCache {
     pub fn new(config: Config) -> Self {
-        let cache_path = PathBuf::from(&config.holiday_data.cache_file);
-        let http_client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+        let store = store::from_config(&config);
+        let source = source::from_config(&config.holiday_data);
         Self {
             config,
-            cache_path,
-            http_client,
+            store,
+            source,
         }
     }
 
-    pub async fn get_holidays(&self) -> Result<HashMap<String, String>> {
-        if self.config.cache.force_refresh_on_startup {
-            return self.download_and_cache().await;
+    /// Keep the cache in `store` instead of the one selected by `cache.backend`
+    pub fn with_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn store(&self) -> &dyn CacheStore {
+        self.store.as_ref()
+    }
+
+    /// Refresh the cache from `source` instead of `holiday_data.source_url`
+    pub fn with_source(mut self, source: Arc<dyn HolidaySource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn source(&self) -> &dyn HolidaySource {
+        self.source.as_ref()
+    }
+
+    /// Holidays from the cache, refreshed from the source according to `config.cache`;
+    /// blocks while the source is fetched
+    pub fn load_holidays(&self) -> Result<CachedHolidays> {
+        Ok(self.get_holiday_names()?.into_cached())
+    }
+
+    /// Download the data now, whatever the strategy; the cache is only replaced when
+    /// the download succeeds and passes the sanity checks
+    pub fn refresh_holidays(&self) -> Result<CachedHolidays> {
+        let _lock = self.store.lock_refresh()?;
+        let (current, discarded) = self.load_current()?;
+        let names = self.download_and_cache(current, false)?;
+        Ok(Names { discarded, ..names }.into_cached())
+    }
+
+    /// Download the data and compare it with the cache like `refresh_holidays`, but
+    /// leave the cache as it is
+    pub fn preview_refresh(&self) -> Result<CachedHolidays> {
+        let (current, discarded) = self.load_current()?;
+        let data = self.source.fetch()?;
+        let diff = self.check_download(&data, current.as_ref())?;
+        Ok(Names {
+            holidays: data.holidays,
+            stale: None,
+            report: data.report,
+            diff: Some(diff),
+            discarded,
         }
+        .into_cached())
+    }
 
-        if !self.cache_path.exists() {
-            return self.download_and_cache().await;
+    /// Holidays from the cache as is, without checking for updates
+    pub fn get_cached_holidays(&self) -> Result<HashMap<NaiveDate, Holiday>> {
+        match self.load_cached()? {
+            Some(cache_data) => Ok(build_holidays(&cache_data.holidays)),
+            None => Err(anyhow::anyhow!(
+                "No cache at {}; download the holiday data first",
+                self.store.describe()
+            )),
         }
+    }
 
-        let cache_data = self.load_cache_data()?;
+    /// The cache contents without refreshing them; `None` when nothing is cached
+    pub fn load_cached(&self) -> Result<Option<CacheData>> {
+        self.store.load()
+    }
 
-        if self.should_refresh_cache(&cache_data.metadata).await? {
-            return self.download_and_cache().await;
+    /// Drop the cached data, so the next load downloads it again
+    pub fn clear(&self) -> Result<()> {
+        self.store.clear()
+    }
+
+    /// Decode CSV bytes with the encoding from `parse::detect_encoding` (a byte order mark,
+    /// valid UTF-8, or Shift_JIS as published by the Cabinet Office), replacing malformed input
+    pub fn decode_csv(bytes: &[u8]) -> String {
+        parse::detect_encoding(bytes).decode(bytes).0.into_owned()
+    }
+
+    /// Parse the Cabinet Office CSV into a date -> holiday name map; see
+    /// `parse::parse_text` for the header check and the rows that are skipped
+    pub fn parse_csv(csv_content: &str) -> Result<HashMap<NaiveDate, String>> {
+        Ok(parse::parse_text(csv_content)?.holidays)
+    }
+}
+
+/// Refreshing the cache from the source
+impl HolidayCache {
+    fn get_holiday_names(&self) -> Result<Names> {
+        let (Some(cache_data), _) = self.load_current()? else {
+            return self.refresh(None);
+        };
+
+        let force = self.config.cache.force_refresh_on_startup;
+        if !force && !self.should_refresh_cache(&cache_data.metadata)? {
+            return Ok(Names::cached(cache_data.holidays));
         }
 
-        Ok(cache_data.holidays)
+        let last_updated = cache_data.metadata.last_updated;
+        let error = match self.refresh(Some(last_updated)) {
+            Ok(names) => return Ok(names),
+            Err(error) => error,
+        };
+        match self.config.cache.on_refresh_error {
+            RefreshErrorPolicy::Fail => Err(error),
+            // A failed revalidation of data that is not yet too old is not worth a warning
+            RefreshErrorPolicy::ServeStale
+                if !force && self.is_revalidation(&cache_data.metadata) =>
+            {
+                Ok(Names::cached(cache_data.holidays))
+            }
+            RefreshErrorPolicy::ServeStale => {
+                let stale = Staleness {
+                    last_updated,
+                    error: error.to_string(),
+                };
+                Ok(Names {
+                    stale: Some(stale),
+                    ..Names::cached(cache_data.holidays)
+                })
+            }
+        }
     }
 
-    fn load_cache_data(&self) -> Result<CacheData> {
-        let content =
-            std::fs::read_to_string(&self.cache_path).context("Failed to read cache file")?;
+    /// Download under the store's refresh lock; if another process refreshed the
+    /// cache while we waited (`last_updated` moved on from `seen`), use its data instead
+    fn refresh(&self, seen: Option<DateTime<Utc>>) -> Result<Names> {
+        let _lock = self.store.lock_refresh()?;
 
-        let cache_data: CacheData =
-            serde_json::from_str(&content).context("Failed to parse cache file")?;
+        let (current, discarded) = self.load_current()?;
+        if let Some(current) = &current {
+            if Some(current.metadata.last_updated) != seen {
+                return Ok(Names::cached(current.holidays.clone()));
+            }
+        }
+
+        let conditional = !self.config.cache.force_refresh_on_startup;
+        let names = self.download_and_cache(current, conditional)?;
+        Ok(Names { discarded, ..names })
+    }
+
+    /// The stored cache, with a cache that cannot be parsed, e.g. a truncated file,
+    /// treated as missing so it is downloaded again; the reason is returned with it.
+    /// Other errors, such as a cache that cannot be read, are passed on.
+    fn load_current(&self) -> Result<(Option<CacheData>, Option<String>)> {
+        match self.store.load() {
+            Ok(current) => Ok((current, None)),
+            Err(error) => match error.downcast::<CorruptCache>() {
+                Ok(corrupt) => Ok((None, Some(corrupt.to_string()))),
+                Err(error) => Err(error),
+            },
+        }
+    }
 
-        Ok(cache_data)
+    /// True when the cache is only being checked for updates, not refreshed because of its age
+    fn is_revalidation(&self, metadata: &CacheMetadata) -> bool {
+        matches!(
+            self.config.cache.strategy,
+            CacheStrategy::EtagBased | CacheStrategy::Hybrid
+        ) && self.get_cache_age_hours(metadata) <= self.config.cache.max_age_hours
     }
 
-    async fn should_refresh_cache(&self, metadata: &CacheMetadata) -> Result<bool> {
+    fn should_refresh_cache(&self, metadata: &CacheMetadata) -> Result<bool> {
         match &self.config.cache.strategy {
             CacheStrategy::AlwaysRefresh => Ok(true),
             CacheStrategy::NeverRefresh => Ok(false),
             CacheStrategy::TimeBased => self.should_refresh_time_based(metadata),
-            CacheStrategy::EtagBased => self.should_refresh_etag_based(metadata).await,
-            CacheStrategy::Hybrid => self.should_refresh_hybrid(metadata).await,
+            CacheStrategy::EtagBased => self.should_refresh_etag_based(metadata),
+            CacheStrategy::Hybrid => self.should_refresh_hybrid(metadata),
         }
     }
 
@@ -83,18 +259,15 @@ impl HolidayCache {
         Ok(cache_age_hours > max_age_hours)
     }
 
-    async fn should_refresh_etag_based(&self, metadata: &CacheMetadata) -> Result<bool> {
-        if metadata.etag.is_none() {
-            return self.should_refresh_time_based(metadata);
-        }
-
-        match self.check_remote_etag().await {
-            Ok(Some(remote_etag)) => Ok(metadata.etag.as_ref() != Some(&remote_etag)),
-            Ok(None) | Err(_) => self.should_refresh_time_based(metadata),
+    /// With validators the refresh is a conditional fetch, which is cheap when nothing changed
+    fn should_refresh_etag_based(&self, metadata: &CacheMetadata) -> Result<bool> {
+        if metadata.has_validators() {
+            return Ok(true);
         }
+        self.should_refresh_time_based(metadata)
     }
 
-    async fn should_refresh_hybrid(&self, metadata: &CacheMetadata) -> Result<bool> {
+    fn should_refresh_hybrid(&self, metadata: &CacheMetadata) -> Result<bool> {
         let cache_age_hours = self.get_cache_age_hours(metadata);
 
         // Force refresh if cache is too old
@@ -112,8 +285,8 @@ impl HolidayCache {
         };
 
         if should_check_etag {
-            // Perform ETag check and update timestamp
-            return self.should_refresh_etag_based(metadata).await;
+            // Revalidate with a conditional fetch, which updates the timestamp
+            return self.should_refresh_etag_based(metadata);
         }
 
         Ok(false)
@@ -125,90 +298,480 @@ impl HolidayCache {
         duration.num_hours() as u64
     }
 
-    async fn check_remote_etag(&self) -> Result<Option<String>> {
-        let response = self
-            .http_client
-            .head(&self.config.holiday_data.source_url)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await?;
+    /// Fetch the data, conditionally when `conditional` and `cached` is given: if the
+    /// source reports it unchanged, only the timestamps of `cached` are updated.
+    /// Data failing `config.cache.sanity` is refused with a `SanityError`, keeping the cache.
+    fn download_and_cache(&self, cached: Option<CacheData>, conditional: bool) -> Result<Names> {
+        let now = Utc::now();
+        let data = match &cached {
+            Some(cached) if conditional => {
+                match self
+                    .source
+                    .fetch_if_modified(&cached.metadata.validators())?
+                {
+                    Some(data) => data,
+                    None => {
+                        // Unchanged: record the check without rewriting the holidays
+                        let metadata = CacheMetadata {
+                            last_updated: now,
+                            last_etag_check: Some(now),
+                            ..cached.metadata.clone()
+                        };
+                        self.store.save_metadata(&metadata)?;
+                        return Ok(Names::cached(cached.holidays.clone()));
+                    }
+                }
+            }
+            _ => self.source.fetch()?,
+        };
+
+        let diff = self.check_download(&data, cached.as_ref())?;
+        let report = data.report.clone();
+        let cache_data = Self::fresh_cache_data(data, now);
+        self.store.save(&cache_data)?;
+
+        Ok(Names {
+            holidays: cache_data.holidays,
+            stale: None,
+            report,
+            diff: Some(diff),
+            discarded: None,
+        })
+    }
 
-        if response.status().is_success() {
-            let etag = response
-                .headers()
-                .get("etag")
-                .and_then(|h| h.to_str().ok())
-                .map(|s| s.to_string());
+    /// Run the sanity checks on downloaded data and compare it with `cached`
+    fn check_download(&self, data: &SourceData, cached: Option<&CacheData>) -> Result<HolidayDiff> {
+        let previous = cached.map(|cached| &cached.holidays);
+        sanity::check(
+            &self.config.cache.sanity,
+            &data.holidays,
+            data.report.as_ref(),
+            previous,
+            Local::now().date_naive(),
+        )?;
+        Ok(HolidayDiff::between(
+            previous.unwrap_or(&HashMap::new()),
+            &data.holidays,
+        ))
+    }
 
-            Ok(etag)
-        } else {
-            Err(anyhow::anyhow!("HTTP error: {}", response.status()))
+    fn fresh_cache_data(data: SourceData, now: DateTime<Utc>) -> CacheData {
+        CacheData {
+            metadata: CacheMetadata {
+                last_updated: now,
+                etag: data.metadata.etag,
+                last_etag_check: Some(now),
+                last_modified: data.metadata.last_modified,
+            },
+            holidays: data.holidays,
         }
     }
+}
 
-    async fn download_and_cache(&self) -> Result<HashMap<String, String>> {
-        let response = self
-            .http_client
-            .get(&self.config.holiday_data.source_url)
-            .send()
-            .await?;
+/// Holiday names from the cache or a download, before they are typed
+struct Names {
+    holidays: HashMap<NaiveDate, String>,
+    stale: Option<Staleness>,
+    report: Option<ParseReport>,
+    diff: Option<HolidayDiff>,
+    discarded: Option<String>,
+}
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "Failed to download data: {}",
-                response.status()
-            ));
+impl Names {
+    fn cached(holidays: HashMap<NaiveDate, String>) -> Self {
+        Self {
+            holidays,
+            stale: None,
+            report: None,
+            diff: None,
+            discarded: None,
         }
+    }
 
-        let etag = response
-            .headers()
-            .get("etag")
-            .and_then(|h| h.to_str().ok())
-            .map(|s| s.to_string());
+    fn into_cached(self) -> CachedHolidays {
+        CachedHolidays {
+            holidays: build_holidays(&self.holidays),
+            stale: self.stale,
+            report: self.report,
+            diff: self.diff,
+            discarded: self.discarded,
+        }
+    }
+}
 
-        let body = response.text_with_charset("shift-jis").await?;
-        let holidays = self.parse_csv(&body)?;
+/// Refreshing the cache without blocking the async runtime
+#[cfg(feature = "fetch")]
+impl HolidayCache {
+    pub async fn get_holidays(&self) -> Result<CachedHolidays> {
+        let cache = self.clone();
+        tokio::task::spawn_blocking(move || cache.load_holidays()).await?
+    }
+}
 
-        // Create cache directory if needed
-        if let Some(parent) = self.cache_path.parent() {
-            std::fs::create_dir_all(parent)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sanity::{SanityConfig, SanityError, SanityViolation};
+    use crate::source::SourceData;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Counts downloads, each taking long enough for the others to queue up
+    struct SlowSource {
+        fetches: AtomicUsize,
+    }
+
+    impl HolidaySource for SlowSource {
+        fn describe(&self) -> String {
+            "slow".to_string()
         }
 
-        // Save to cache
-        let now = Utc::now();
-        let cache_data = CacheData {
-            metadata: CacheMetadata {
-                last_updated: now,
-                etag,
-                last_etag_check: Some(now),
-            },
-            holidays: holidays.clone(),
-        };
+        fn fetch(&self) -> Result<SourceData> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(100));
+            Ok(SourceData {
+                holidays: [(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), "元日".into())].into(),
+                metadata: SourceMetadata::default(),
+                report: None,
+            })
+        }
 
-        let json = serde_json::to_string_pretty(&cache_data)?;
-        std::fs::write(&self.cache_path, json)?;
+        fn metadata(&self) -> Result<SourceMetadata> {
+            Ok(SourceMetadata::default())
+        }
+    }
 
-        Ok(holidays)
+    #[test]
+    fn test_concurrent_refreshes_download_once() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.cache.sanity = SanityConfig::disabled();
+        config.holiday_data.cache_file = dir
+            .path()
+            .join("holidays.json")
+            .to_string_lossy()
+            .to_string();
+        let source = Arc::new(SlowSource {
+            fetches: AtomicUsize::new(0),
+        });
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = HolidayCache::new(config.clone()).with_source(source.clone());
+                std::thread::spawn(move || cache.load_holidays().unwrap().holidays.len())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 1);
+        }
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 1);
     }
 
-    fn parse_csv(&self, csv_content: &str) -> Result<HashMap<String, String>> {
-        let mut holidays = HashMap::new();
-        let mut rdr = csv::Reader::from_reader(csv_content.as_bytes());
+    struct FailingSource;
 
-        for result in rdr.records() {
-            let record = result?;
-            if record.len() >= 2 {
-                let date_str = &record[0];
-                let holiday_name = &record[1];
+    impl HolidaySource for FailingSource {
+        fn describe(&self) -> String {
+            "failing".to_string()
+        }
 
-                // 日付を YYYY-MM-DD 形式に変換
-                if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y/%m/%d") {
-                    let formatted_date = date.format("%Y-%m-%d").to_string();
-                    holidays.insert(formatted_date, holiday_name.to_string());
-                }
-            }
+        fn fetch(&self) -> Result<SourceData> {
+            Err(anyhow::anyhow!("503 Service Unavailable"))
+        }
+
+        fn metadata(&self) -> Result<SourceMetadata> {
+            self.fetch().map(|data| data.metadata)
         }
+    }
+
+    #[test]
+    fn test_stale_cache_is_served_when_refresh_fails() {
+        let store = Arc::new(crate::store::MemoryStore::default());
+        let mut config = Config::default();
+        config.cache.strategy = CacheStrategy::AlwaysRefresh;
+        let cache = |config: &Config| {
+            HolidayCache::new(config.clone())
+                .with_store(store.clone())
+                .with_source(Arc::new(FailingSource))
+        };
+
+        // Nothing cached: the error is returned
+        assert!(cache(&config).load_holidays().is_err());
+
+        let last_updated = Utc::now() - chrono::Duration::days(30);
+        store
+            .save(&CacheData {
+                metadata: CacheMetadata {
+                    last_updated,
+                    etag: None,
+                    last_etag_check: None,
+                    last_modified: None,
+                },
+                holidays: [(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), "元日".into())].into(),
+            })
+            .unwrap();
+
+        let loaded = cache(&config).load_holidays().unwrap();
+        assert_eq!(loaded.holidays.len(), 1);
+        let stale = loaded.stale.unwrap();
+        assert_eq!(stale.last_updated, last_updated);
+        assert!(stale.error.contains("503"));
+
+        config.cache.on_refresh_error = RefreshErrorPolicy::Fail;
+        assert!(cache(&config).load_holidays().is_err());
+    }
+
+    #[test]
+    fn test_unmodified_source_only_bumps_timestamps() {
+        let store = Arc::new(crate::store::MemoryStore::default());
+        let last_updated = Utc::now() - chrono::Duration::days(30);
+        let cached_holidays: HashMap<NaiveDate, String> =
+            [(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), "元日".into())].into();
+        store
+            .save(&CacheData {
+                metadata: CacheMetadata {
+                    last_updated,
+                    etag: Some("\"v1\"".to_string()),
+                    last_etag_check: None,
+                    last_modified: None,
+                },
+                holidays: cached_holidays.clone(),
+            })
+            .unwrap();
+
+        // Same ETag, so the (different) data of the source is not fetched
+        let source =
+            crate::source::MemorySource::new(HashMap::new()).with_metadata(SourceMetadata {
+                etag: Some("\"v1\"".to_string()),
+                last_modified: None,
+            });
+        let mut config = Config::default();
+        config.cache.strategy = CacheStrategy::EtagBased;
+        let cache = HolidayCache::new(config)
+            .with_store(store.clone())
+            .with_source(Arc::new(source));
+
+        let loaded = cache.load_holidays().unwrap();
+        assert_eq!(loaded.holidays.len(), 1);
+        assert!(loaded.stale.is_none());
+
+        let saved = store.load().unwrap().unwrap();
+        assert_eq!(saved.holidays, cached_holidays);
+        assert!(saved.metadata.last_updated > last_updated);
+        assert!(saved.metadata.last_etag_check.is_some());
+    }
+
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn test_hybrid_check_is_persisted_across_runs() {
+        use httptest::{all_of, matchers::*, responders::*, Expectation, Server};
+
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/syukujitsu.csv"),
+                request::headers(not(contains(key("if-none-match")))),
+            ])
+            .times(1)
+            .respond_with(
+                status_code(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .body("国民の祝日・休日月日,国民の祝日・休日名称\n2023/1/1,元日\n"),
+            ),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/syukujitsu.csv"),
+                request::headers(contains(("if-none-match", "\"v1\""))),
+            ])
+            .times(1)
+            .respond_with(status_code(304)),
+        );
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.cache.strategy = CacheStrategy::Hybrid;
+        config.cache.sanity = SanityConfig::disabled();
+        config.holiday_data.source_url = server.url_str("/syukujitsu.csv");
+        config.holiday_data.cache_file = dir
+            .path()
+            .join("holidays.json")
+            .to_string_lossy()
+            .to_string();
+        let run = || HolidayCache::new(config.clone()).load_holidays().unwrap();
+
+        // First run downloads, the second is within the check interval
+        assert_eq!(run().holidays.len(), 1);
+        assert_eq!(run().holidays.len(), 1);
+
+        // Once the interval has passed, one conditional GET finds no change...
+        let store = store::from_config(&config);
+        let mut metadata = store.load().unwrap().unwrap().metadata;
+        metadata.last_etag_check = Some(Utc::now() - chrono::Duration::hours(25));
+        store.save_metadata(&metadata).unwrap();
+        let payload = dir.path().join("holidays.json");
+        let read_payload = || {
+            let modified = std::fs::metadata(&payload).unwrap().modified().unwrap();
+            (std::fs::read(&payload).unwrap(), modified)
+        };
+        let before = read_payload();
+        assert_eq!(run().holidays.len(), 1);
+        assert_eq!(read_payload(), before, "the holidays are not rewritten");
+
+        // ...and is recorded, so the following runs make no request at all
+        let checked = store.load().unwrap().unwrap().metadata.last_etag_check;
+        assert!(checked > metadata.last_etag_check);
+        assert_eq!(run().holidays.len(), 1);
+        assert_eq!(run().holidays.len(), 1);
+    }
+
+    #[test]
+    fn test_suspiciously_small_download_keeps_cache() {
+        let store = Arc::new(crate::store::MemoryStore::default());
+        let cached: HashMap<NaiveDate, String> = crate::rules::holidays_in_year(2023)
+            .into_iter()
+            .map(|holiday| (holiday.date, holiday.name))
+            .collect();
+        store
+            .save(&CacheData {
+                metadata: CacheMetadata {
+                    last_updated: Utc::now(),
+                    etag: None,
+                    last_etag_check: None,
+                    last_modified: None,
+                },
+                holidays: cached.clone(),
+            })
+            .unwrap();
+
+        let mut config = Config::default();
+        config.cache.strategy = CacheStrategy::AlwaysRefresh;
+        config.cache.on_refresh_error = RefreshErrorPolicy::Fail;
+        let partial = crate::source::MemorySource::new(
+            cached
+                .iter()
+                .take(3)
+                .map(|(d, n)| (*d, n.clone()))
+                .collect(),
+        );
+        let cache = HolidayCache::new(config)
+            .with_store(store.clone())
+            .with_source(Arc::new(partial));
+
+        let error = cache.load_holidays().unwrap_err();
+        assert!(error.to_string().contains("keeping the cache"));
+        let violations = &error.downcast_ref::<SanityError>().unwrap().violations;
+        assert!(violations.contains(&SanityViolation::Shrunk {
+            previous: cached.len(),
+            current: 3
+        }));
+        assert_eq!(store.load().unwrap().unwrap().holidays, cached);
+    }
+
+    #[test]
+    fn test_refresh_reports_diff_and_preview_keeps_cache() {
+        use crate::diff::HolidayChange;
+
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let store = Arc::new(crate::store::MemoryStore::default());
+        let cached: HashMap<NaiveDate, String> = [(date(1, 1), "元日".to_string())].into();
+        store
+            .save(&CacheData {
+                metadata: CacheMetadata {
+                    last_updated: Utc::now(),
+                    etag: None,
+                    last_etag_check: None,
+                    last_modified: None,
+                },
+                holidays: cached.clone(),
+            })
+            .unwrap();
+
+        let mut config = Config::default();
+        config.cache.sanity = SanityConfig::disabled();
+        let source = crate::source::MemorySource::new(
+            [
+                (date(1, 1), "元日".to_string()),
+                (date(2, 11), "建国記念の日".to_string()),
+            ]
+            .into(),
+        );
+        let cache = HolidayCache::new(config)
+            .with_store(store.clone())
+            .with_source(Arc::new(source));
+        let added = vec![HolidayChange::Added {
+            date: date(2, 11),
+            name: "建国記念の日".to_string(),
+        }];
+
+        let preview = cache.preview_refresh().unwrap();
+        assert_eq!(preview.diff.unwrap().changes, added);
+        assert_eq!(store.load().unwrap().unwrap().holidays, cached);
+
+        let refreshed = cache.refresh_holidays().unwrap();
+        assert_eq!(refreshed.diff.unwrap().changes, added);
+        assert_eq!(store.load().unwrap().unwrap().holidays.len(), 2);
+        assert!(cache.refresh_holidays().unwrap().diff.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_cache_is_downloaded_again() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("holidays.json");
+        let mut config = Config::default();
+        config.cache.sanity = SanityConfig::disabled();
+        config.holiday_data.cache_file = path.to_string_lossy().to_string();
+        let source = Arc::new(SlowSource {
+            fetches: AtomicUsize::new(0),
+        });
+        let cache = HolidayCache::new(config).with_source(source.clone());
+
+        cache.load_holidays().unwrap();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(10).unwrap();
+
+        let loaded = cache.load_holidays().unwrap();
+        assert_eq!(loaded.holidays.len(), 1);
+        assert!(loaded.discarded.unwrap().contains("corrupt"));
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 2);
+        assert!(cache.load_cached().unwrap().is_some());
+
+        // A cache that cannot be read at all is an error, not a reason to download
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir(&path).unwrap();
+        assert!(cache.load_holidays().is_err());
+        assert!(cache.refresh_holidays().is_err());
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_failed_revalidation_honors_fail_policy() {
+        let store = Arc::new(crate::store::MemoryStore::default());
+        store
+            .save(&CacheData {
+                metadata: CacheMetadata {
+                    last_updated: Utc::now(),
+                    etag: Some("\"v1\"".to_string()),
+                    last_etag_check: None,
+                    last_modified: None,
+                },
+                holidays: [(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), "元日".into())].into(),
+            })
+            .unwrap();
+        let mut config = Config::default();
+        config.cache.strategy = CacheStrategy::EtagBased;
+        let cache = |config: &Config| {
+            HolidayCache::new(config.clone())
+                .with_store(store.clone())
+                .with_source(Arc::new(FailingSource))
+        };
+
+        // Fresh data whose revalidation fails is served silently...
+        let loaded = cache(&config).load_holidays().unwrap();
+        assert!(loaded.stale.is_none());
 
-        Ok(holidays)
+        // ...unless failures are configured to be errors
+        config.cache.on_refresh_error = RefreshErrorPolicy::Fail;
+        let error = cache(&config).load_holidays().unwrap_err();
+        assert!(error.to_string().contains("503"));
     }
 }
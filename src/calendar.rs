@@ -0,0 +1,129 @@
+//! Named holiday calendars derived from the national holidays.
+//!
+//! Banks (銀行法施行令) and the Tokyo Stock Exchange (業務規程) are
+//! closed on the national holidays and additionally from December 31 to January 3.
+//! These year-end closures are not in the Cabinet Office CSV.
+
+use crate::holiday::{Holiday, HolidayKind, HolidayOrigin};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A holiday calendar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Calendar {
+    /// National holidays only
+    #[default]
+    National,
+    /// Bank holidays, as used for Zengin settlement
+    Banking,
+    /// Tokyo Stock Exchange closed days
+    Tse,
+}
+
+/// Year-end closure days as (month, day); January 1 is already a national holiday
+const YEAR_END_CLOSURE: &[(u32, u32)] = &[(1, 2), (1, 3), (12, 31)];
+
+impl Calendar {
+    pub const ALL: [Calendar; 3] = [Calendar::National, Calendar::Banking, Calendar::Tse];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Calendar::National => "national",
+            Calendar::Banking => "banking",
+            Calendar::Tse => "tse",
+        }
+    }
+
+    fn closure_name(&self) -> Option<&'static str> {
+        match self {
+            Calendar::National => None,
+            Calendar::Banking => Some("銀行休業日"),
+            Calendar::Tse => Some("東証休業日"),
+        }
+    }
+
+    /// The closure this calendar adds on `date` on top of the national holidays
+    pub fn closure_on(&self, date: NaiveDate) -> Option<Holiday> {
+        let name = self.closure_name()?;
+        YEAR_END_CLOSURE
+            .contains(&(date.month(), date.day()))
+            .then(|| {
+                Holiday::new(date, name, HolidayKind::Closure)
+                    .with_origin(HolidayOrigin::Calendar { calendar: *self })
+            })
+    }
+
+    /// Closures this calendar adds between `start` and `end`, inclusive, in date order
+    pub fn closures_between(&self, start: NaiveDate, end: NaiveDate) -> Vec<Holiday> {
+        if self.closure_name().is_none() {
+            return Vec::new();
+        }
+        (start.year()..=end.year())
+            .flat_map(|year| {
+                YEAR_END_CLOSURE
+                    .iter()
+                    .filter_map(move |&(month, day)| NaiveDate::from_ymd_opt(year, month, day))
+            })
+            .filter(|date| *date >= start && *date <= end)
+            .filter_map(|date| self.closure_on(date))
+            .collect()
+    }
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Calendar {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Calendar::ALL
+            .into_iter()
+            .find(|calendar| calendar.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown calendar '{}': expected national, banking or tse",
+                    s
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_closures() {
+        assert_eq!(Calendar::National.closure_on(date(2023, 12, 31)), None);
+
+        let closure = Calendar::Banking.closure_on(date(2023, 12, 31)).unwrap();
+        assert_eq!(closure.name, "銀行休業日");
+        assert_eq!(closure.kind, HolidayKind::Closure);
+        assert_eq!(Calendar::Tse.closure_on(date(2024, 1, 1)), None);
+
+        let dates: Vec<NaiveDate> = Calendar::Tse
+            .closures_between(date(2023, 12, 1), date(2024, 1, 2))
+            .into_iter()
+            .map(|h| h.date)
+            .collect();
+        assert_eq!(dates, vec![date(2023, 12, 31), date(2024, 1, 2)]);
+    }
+
+    #[test]
+    fn test_parse_calendar() {
+        assert_eq!("banking".parse::<Calendar>().unwrap(), Calendar::Banking);
+        assert_eq!("TSE".parse::<Calendar>().unwrap(), Calendar::Tse);
+        assert!("nyse".parse::<Calendar>().is_err());
+    }
+}
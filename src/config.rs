@@ -1,16 +1,65 @@
+use crate::business_day::WorkCalendar;
 use crate::constants::*;
+use crate::sanity::SanityConfig;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub holiday_data: HolidayDataConfig,
     pub cache: CacheConfig,
+    #[serde(default)]
+    pub work_calendar: WorkCalendar,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HolidayDataConfig {
     pub source_url: String,
     pub cache_file: String,
+    /// Local CSV/TOML files adding or removing holidays on top of the data
+    #[serde(default)]
+    pub overlays: Vec<String>,
+    /// How `source_url` is downloaded when it is an HTTP(S) URL
+    #[serde(default)]
+    pub http: HttpConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Timeout of a download, in seconds
+    pub timeout_secs: u64,
+    /// Timeout of a check for updates (a conditional GET, or a HEAD request), in seconds
+    pub check_timeout_secs: u64,
+    /// Retries after a connection error, a timeout, 429 or a 5xx response
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each further retry, in milliseconds
+    pub backoff_ms: u64,
+    /// Upper bound of the retry delay, in milliseconds
+    pub max_backoff_ms: u64,
+    /// `User-Agent` header; defaults to `holidays_jp/<version>`
+    pub user_agent: Option<String>,
+    /// Proxy URL for all requests, e.g. `http://proxy.example.com:8080`
+    pub proxy: Option<String>,
+    /// PEM files with additional trusted CA certificates
+    pub ca_certificates: Vec<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_HTTP_TIMEOUT_SECS,
+            check_timeout_secs: DEFAULT_HTTP_CHECK_TIMEOUT_SECS,
+            retries: DEFAULT_HTTP_RETRIES,
+            backoff_ms: DEFAULT_HTTP_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_HTTP_MAX_BACKOFF_MS,
+            user_agent: None,
+            proxy: None,
+            ca_certificates: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +68,15 @@ pub struct CacheConfig {
     pub max_age_hours: u64,
     pub etag_check_interval_hours: u64,
     pub force_refresh_on_startup: bool,
+    /// Where the cache is stored; `holiday_data.cache_file` is the JSON file or SQLite database
+    #[serde(default)]
+    pub backend: CacheBackend,
+    /// What to do when a refresh is due but the download fails
+    #[serde(default)]
+    pub on_refresh_error: RefreshErrorPolicy,
+    /// Invariants downloaded data must meet before it replaces the cache
+    #[serde(default)]
+    pub sanity: SanityConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,19 +88,46 @@ pub enum CacheStrategy {
     NeverRefresh,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheBackend {
+    /// A pretty-printed JSON file
+    #[default]
+    JsonFile,
+    /// In memory only, lost when the process exits
+    Memory,
+    /// An SQLite database (`sqlite` feature)
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefreshErrorPolicy {
+    /// Keep serving the existing cache, marked as stale unless only an update check of
+    /// data younger than `max_age_hours` failed; fail only when nothing is cached
+    #[default]
+    ServeStale,
+    /// Return the error of any failed refresh or update check
+    Fail,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             holiday_data: HolidayDataConfig {
                 source_url: DEFAULT_SOURCE_URL.to_string(),
-                cache_file: DEFAULT_CACHE_FILE.to_string(),
+                cache_file: default_cache_file(),
+                overlays: Vec::new(),
+                http: HttpConfig::default(),
             },
             cache: CacheConfig {
                 strategy: CacheStrategy::Hybrid,
                 max_age_hours: 168, // 7 days - aligns with weekly GitHub Actions updates
                 etag_check_interval_hours: 24, // Daily ETag check for emergency updates
                 force_refresh_on_startup: false,
+                backend: CacheBackend::default(),
+                on_refresh_error: RefreshErrorPolicy::default(),
+                sanity: SanityConfig::default(),
             },
+            work_calendar: WorkCalendar::default(),
         }
     }
 }
@@ -52,36 +137,650 @@ impl Config {
         Self::load_with_verbosity(false)
     }
 
+    /// Load the configuration like `Config::builder().build()`: the first config file in
+    /// `search_paths` (or the defaults when there is none) with `HOLIDAYS_JP_*` overrides.
+    /// Nothing is written; `init` creates the file.
     pub fn load_with_verbosity(verbose: bool) -> anyhow::Result<Self> {
-        // 設定ファイルがあれば読み込み、なければデフォルト設定ファイルを生成
-        if std::path::Path::new(CONFIG_FILE_NAME).exists() {
-            if verbose {
-                println!("📄 Loading configuration from config.toml");
+        Self::builder().with_verbosity(verbose).build()
+    }
+
+    /// Layer the defaults, a config file, `HOLIDAYS_JP_*` variables and explicit values
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Read and parse the config file at `path`; relative overlay paths in it are
+    /// resolved against its directory
+    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
+        let file = read_file(path)?;
+        toml::Value::Table(file)
+            .try_into()
+            .with_context(|| format!("Invalid configuration in {}", path.display()))
+    }
+
+    /// Config files in the order they are searched: `./holidays_jp.toml`,
+    /// `$XDG_CONFIG_HOME/holidays_jp/config.toml` (`~/.config` by default), then the
+    /// same under each of `$XDG_CONFIG_DIRS` (`/etc/xdg` by default)
+    pub fn search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(LOCAL_CONFIG_FILE_NAME)];
+        paths.extend(Self::user_config_path());
+        let config_dirs = env::var_os("XDG_CONFIG_DIRS")
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".into());
+        paths.extend(
+            env::split_paths(&config_dirs)
+                .filter(|dir| dir.is_absolute())
+                .map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME)),
+        );
+        paths
+    }
+
+    /// The first of `search_paths` that exists
+    pub fn find_file() -> Option<PathBuf> {
+        Self::search_paths().into_iter().find(|path| path.is_file())
+    }
+
+    /// Where `init` writes the config file: `$XDG_CONFIG_HOME/holidays_jp/config.toml`
+    pub fn user_config_path() -> Option<PathBuf> {
+        xdg_home("XDG_CONFIG_HOME", ".config")
+            .map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+    }
+
+    /// Write the default configuration to `path`, creating its directory. An existing
+    /// file is only replaced with `force`.
+    pub fn init(path: &Path, force: bool) -> anyhow::Result<()> {
+        if path.exists() && !force {
+            return Err(anyhow::anyhow!(
+                "{} already exists; use --force to overwrite it",
+                path.display()
+            ));
+        }
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let toml_content = toml::to_string_pretty(&Config::default())?;
+        std::fs::write(path, toml_content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Fields overridden by `HOLIDAYS_JP_*` variables, by TOML key; the variable is the key
+/// in upper case with `_` for `.`, e.g. `HOLIDAYS_JP_CACHE_MAX_AGE_HOURS`
+const ENV_KEYS: &[(&str, EnvKind)] = &[
+    ("holiday_data.source_url", EnvKind::Text),
+    ("holiday_data.cache_file", EnvKind::Text),
+    ("holiday_data.overlays", EnvKind::Paths),
+    ("holiday_data.http.timeout_secs", EnvKind::Integer),
+    ("holiday_data.http.check_timeout_secs", EnvKind::Integer),
+    ("holiday_data.http.retries", EnvKind::Integer),
+    ("holiday_data.http.backoff_ms", EnvKind::Integer),
+    ("holiday_data.http.max_backoff_ms", EnvKind::Integer),
+    ("holiday_data.http.user_agent", EnvKind::OptionalText),
+    ("holiday_data.http.proxy", EnvKind::OptionalText),
+    ("holiday_data.http.ca_certificates", EnvKind::Paths),
+    ("cache.strategy", EnvKind::Text),
+    ("cache.max_age_hours", EnvKind::Integer),
+    ("cache.etag_check_interval_hours", EnvKind::Integer),
+    ("cache.force_refresh_on_startup", EnvKind::Boolean),
+    ("cache.backend", EnvKind::Text),
+    ("cache.on_refresh_error", EnvKind::Text),
+    ("cache.sanity.min_holidays_per_year", EnvKind::Integer),
+    ("cache.sanity.no_year_regressions", EnvKind::Boolean),
+    ("cache.sanity.require_current_year", EnvKind::Boolean),
+    ("cache.sanity.unique_dates", EnvKind::Boolean),
+    ("cache.sanity.min_replacement_ratio", EnvKind::Float),
+];
+
+/// How the value of a `HOLIDAYS_JP_*` variable is read
+#[derive(Debug, Clone, Copy)]
+enum EnvKind {
+    Text,
+    /// Empty unsets the value
+    OptionalText,
+    Integer,
+    Float,
+    /// `true`/`false`, `1`/`0`, `yes`/`no` or `on`/`off`
+    Boolean,
+    /// Separated like `PATH` (`:`, or `;` on Windows)
+    Paths,
+}
+
+/// The environment variable overriding the TOML `key`
+pub fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// Builds a `Config` from layers, each overriding the one before: the defaults, a config
+/// file, `HOLIDAYS_JP_*` environment variables, then values set on the builder, such as
+/// `with_cache_strategy` (the CLI's own flags)
+///
+/// ```no_run
+/// use holidays_jp::config::CacheStrategy;
+/// use holidays_jp::Config;
+///
+/// let config = Config::builder()
+///     .with_file("/etc/holidays_jp.toml")
+///     .with_cache_strategy(CacheStrategy::NeverRefresh)
+///     .build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    file: Option<PathBuf>,
+    search: bool,
+    env: bool,
+    values: Vec<(&'static str, toml::Value)>,
+    verbose: bool,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self {
+            file: None,
+            search: true,
+            env: true,
+            values: Vec::new(),
+            verbose: false,
+        }
+    }
+}
+
+impl ConfigBuilder {
+    /// Load `path`, which must exist, instead of searching `Config::search_paths`
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Load no config file, not even one named by `HOLIDAYS_JP_CONFIG`
+    pub fn without_file(mut self) -> Self {
+        self.file = None;
+        self.search = false;
+        self
+    }
+
+    /// Whether `HOLIDAYS_JP_*` variables are applied (default true)
+    pub fn with_env(mut self, env: bool) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// `holiday_data.source_url`
+    pub fn with_source_url(self, url: impl Into<String>) -> Self {
+        self.with_key("holiday_data.source_url", url.into())
+    }
+
+    /// `holiday_data.cache_file`
+    pub fn with_cache_file(self, path: impl Into<String>) -> Self {
+        self.with_key("holiday_data.cache_file", path.into())
+    }
+
+    /// `holiday_data.overlays`
+    pub fn with_overlays<I, S>(self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let paths: Vec<String> = paths.into_iter().map(Into::into).collect();
+        self.with_key("holiday_data.overlays", paths)
+    }
+
+    /// `cache.strategy`
+    pub fn with_cache_strategy(self, strategy: CacheStrategy) -> Self {
+        self.with_key("cache.strategy", enum_value(&strategy))
+    }
+
+    /// `cache.max_age_hours`
+    pub fn with_max_age_hours(self, hours: u64) -> Self {
+        self.with_key("cache.max_age_hours", hours as i64)
+    }
+
+    /// `cache.etag_check_interval_hours`
+    pub fn with_etag_check_interval_hours(self, hours: u64) -> Self {
+        self.with_key("cache.etag_check_interval_hours", hours as i64)
+    }
+
+    /// `cache.force_refresh_on_startup`
+    pub fn with_force_refresh_on_startup(self, force: bool) -> Self {
+        self.with_key("cache.force_refresh_on_startup", force)
+    }
+
+    /// `cache.backend`
+    pub fn with_cache_backend(self, backend: CacheBackend) -> Self {
+        self.with_key("cache.backend", enum_value(&backend))
+    }
+
+    /// `cache.on_refresh_error`
+    pub fn with_refresh_error_policy(self, policy: RefreshErrorPolicy) -> Self {
+        self.with_key("cache.on_refresh_error", enum_value(&policy))
+    }
+
+    /// Set the TOML `key`, one of `ENV_KEYS`, over every other layer
+    fn with_key(mut self, key: &'static str, value: impl Into<toml::Value>) -> Self {
+        debug_assert!(ENV_KEYS.iter().any(|(known, _)| *known == key), "{}", key);
+        self.values.push((key, value.into()));
+        self
+    }
+
+    /// Print which file is loaded
+    pub fn with_verbosity(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// The config file `build` loads: the one given to `with_file`, else the one named by
+    /// `HOLIDAYS_JP_CONFIG`, else the first of `Config::search_paths` that exists
+    pub fn config_file(&self) -> Option<PathBuf> {
+        self.config_file_with(|name| env::var(name).ok())
+    }
+
+    pub fn build(self) -> anyhow::Result<Config> {
+        self.build_with(|name| env::var(name).ok())
+    }
+
+    fn config_file_with(&self, var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+        if self.file.is_some() || !self.search {
+            return self.file.clone();
+        }
+        let from_env = if self.env { var(CONFIG_PATH_ENV) } else { None };
+        match from_env.filter(|path| !path.is_empty()) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Config::find_file(),
+        }
+    }
+
+    fn build_with(self, var: impl Fn(&str) -> Option<String>) -> anyhow::Result<Config> {
+        let mut config = toml::Value::try_from(Config::default())?;
+
+        match self.config_file_with(&var) {
+            Some(path) => {
+                if self.verbose {
+                    println!("📄 Loading configuration from {}", path.display());
+                }
+                let file = read_file(&path)?;
+                merge(&mut config, toml::Value::Table(file));
             }
-            let content = std::fs::read_to_string(CONFIG_FILE_NAME)?;
-            let config: Config = toml::from_str(&content)?;
-            if verbose {
-                println!("   Source URL: {}", config.holiday_data.source_url);
-                println!("   Cache file: {}", config.holiday_data.cache_file);
-                println!("   Cache strategy: {:?}", config.cache.strategy);
+            None if self.verbose => println!("📄 No configuration file found; using the defaults"),
+            None => {}
+        }
+
+        if self.env {
+            for (key, kind) in ENV_KEYS {
+                let name = env_var_name(key);
+                if let Some(raw) = var(&name) {
+                    let value = parse_env_value(&raw, *kind).map_err(|error| {
+                        anyhow::anyhow!("Invalid {}='{}': {}", name, raw, error)
+                    })?;
+                    set_key(&mut config, key, value)?;
+                }
             }
-            Ok(config)
-        } else {
-            if verbose {
-                println!("📄 Creating default config.toml file");
+        }
+
+        for (key, value) in self.values {
+            set_key(&mut config, key, Some(value))?;
+        }
+
+        let config: Config = config
+            .try_into()
+            .map_err(|error| anyhow::anyhow!("Invalid configuration: {}", error))?;
+        if self.verbose {
+            println!("   Source URL: {}", config.holiday_data.source_url);
+            println!("   Cache file: {}", config.holiday_data.cache_file);
+            println!("   Cache strategy: {:?}", config.cache.strategy);
+        }
+        Ok(config)
+    }
+}
+
+/// The TOML table in the config file at `path`, with relative `holiday_data.overlays`
+/// made relative to the file's directory rather than the current one
+fn read_file(path: &Path) -> anyhow::Result<toml::Table> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut file: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Invalid configuration in {}", path.display()))?;
+
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let overlays = file
+        .get_mut("holiday_data")
+        .and_then(|data| data.get_mut("overlays"))
+        .and_then(|overlays| overlays.as_array_mut());
+    if let (Some(dir), Some(overlays)) = (dir, overlays) {
+        for overlay in overlays {
+            if let toml::Value::String(overlay) = overlay {
+                let relative = Path::new(overlay.as_str());
+                if relative.is_relative() {
+                    let relative = relative.strip_prefix(".").unwrap_or(relative);
+                    *overlay = dir.join(relative).to_string_lossy().into_owned();
+                }
             }
-            Self::create_default_config_file(verbose)?;
-            Ok(Config::default())
         }
     }
+    Ok(file)
+}
 
-    fn create_default_config_file(verbose: bool) -> anyhow::Result<()> {
-        let default_config = Config::default();
-        let toml_content = toml::to_string_pretty(&default_config)?;
-        std::fs::write(CONFIG_FILE_NAME, toml_content)?;
-        if verbose {
-            println!("✅ Created default config.toml file");
+/// `None` unsets the key
+fn parse_env_value(raw: &str, kind: EnvKind) -> anyhow::Result<Option<toml::Value>> {
+    let value = match kind {
+        EnvKind::Text => toml::Value::String(raw.to_string()),
+        EnvKind::OptionalText if raw.is_empty() => return Ok(None),
+        EnvKind::OptionalText => toml::Value::String(raw.to_string()),
+        EnvKind::Integer => {
+            toml::Value::Integer(raw.trim().parse().context("expected an integer")?)
         }
-        Ok(())
+        EnvKind::Float => toml::Value::Float(raw.trim().parse().context("expected a number")?),
+        EnvKind::Boolean => toml::Value::Boolean(match raw.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => true,
+            "false" | "0" | "no" | "off" => false,
+            _ => return Err(anyhow::anyhow!("expected true or false")),
+        }),
+        EnvKind::Paths => toml::Value::Array(
+            env::split_paths(raw)
+                .filter(|path| !path.as_os_str().is_empty())
+                .map(|path| toml::Value::String(path.to_string_lossy().into_owned()))
+                .collect(),
+        ),
+    };
+    Ok(Some(value))
+}
+
+/// Merge `layer` into `base`: tables key by key, anything else replaced
+fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// The TOML string of a unit enum variant, e.g. `"Hybrid"`
+fn enum_value(value: &impl Serialize) -> toml::Value {
+    toml::Value::try_from(value).expect("unit variants serialize to strings")
+}
+
+/// Set the dotted `key` in `config`, creating tables on the way; `None` removes it.
+/// Fails when a value on the way is not a table, e.g. `cache = 1` in the config file.
+fn set_key(config: &mut toml::Value, key: &str, value: Option<toml::Value>) -> anyhow::Result<()> {
+    let mut table = config;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let toml::Value::Table(parent) = table else {
+            return Err(anyhow::anyhow!(
+                "Cannot set {}: the value on the way is not a table",
+                key
+            ));
+        };
+        if parts.peek().is_none() {
+            match value {
+                Some(value) => {
+                    parent.insert(part.to_string(), value);
+                }
+                None => {
+                    parent.remove(part);
+                }
+            }
+            return Ok(());
+        }
+        table = parent
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    }
+    Ok(())
+}
+
+/// `$XDG_CACHE_HOME/holidays_jp/holidays.json` (`~/.cache` by default)
+fn default_cache_file() -> String {
+    match xdg_home("XDG_CACHE_HOME", ".cache") {
+        Some(dir) => dir
+            .join(APP_DIR_NAME)
+            .join(DEFAULT_CACHE_FILE_NAME)
+            .to_string_lossy()
+            .into_owned(),
+        None => FALLBACK_CACHE_FILE.to_string(),
+    }
+}
+
+/// The XDG base directory in `var`, or `fallback` under the home directory.
+/// Relative paths are ignored, as the specification requires.
+fn xdg_home(var: &str, fallback: &str) -> Option<PathBuf> {
+    let home = env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    resolve_xdg_home(env::var_os(var).map(PathBuf::from), home, fallback)
+}
+
+fn resolve_xdg_home(
+    value: Option<PathBuf>,
+    home: Option<PathBuf>,
+    fallback: &str,
+) -> Option<PathBuf> {
+    value.filter(|dir| dir.is_absolute()).or_else(|| {
+        home.filter(|home| home.is_absolute())
+            .map(|home| home.join(fallback))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_xdg_home_resolution() {
+        let home = Some(PathBuf::from("/home/user"));
+        assert_eq!(
+            resolve_xdg_home(Some("/xdg/config".into()), home.clone(), ".config"),
+            Some(PathBuf::from("/xdg/config"))
+        );
+        // Unset or relative: the fallback under the home directory
+        for value in [None, Some(PathBuf::from("relative"))] {
+            assert_eq!(
+                resolve_xdg_home(value, home.clone(), ".config"),
+                Some(PathBuf::from("/home/user/.config"))
+            );
+        }
+        assert_eq!(resolve_xdg_home(None, None, ".cache"), None);
+
+        // A generic ./config.toml of another program is never picked up
+        let paths = Config::search_paths();
+        assert_eq!(paths[0], PathBuf::from(LOCAL_CONFIG_FILE_NAME));
+        assert!(!paths.contains(&PathBuf::from(CONFIG_FILE_NAME)));
+    }
+
+    #[test]
+    fn test_init_writes_loadable_config_once() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join(APP_DIR_NAME).join(CONFIG_FILE_NAME);
+
+        Config::init(&path, false).unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.holiday_data.source_url, DEFAULT_SOURCE_URL);
+
+        std::fs::write(&path, "").unwrap();
+        assert!(Config::init(&path, false).is_err());
+        assert!(Config::load_from(&path).is_err());
+        Config::init(&path, true).unwrap();
+        assert!(Config::load_from(&path).is_ok());
+    }
+
+    #[test]
+    fn test_example_matches_defaults() {
+        let example = concat!(env!("CARGO_MANIFEST_DIR"), "/config.toml.example");
+        let mut config = Config::load_from(Path::new(example)).unwrap();
+        // The example shows a typical path, not the one of whoever runs the test
+        config.holiday_data.cache_file = default_cache_file();
+        assert_eq!(
+            toml::Value::try_from(&config).unwrap(),
+            toml::Value::try_from(Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_env_keys_cover_every_field() {
+        let mut config = Config::default();
+        config.holiday_data.http.user_agent = Some("agent".to_string());
+        config.holiday_data.http.proxy = Some("http://proxy".to_string());
+
+        fn keys(prefix: &str, value: &toml::Value, out: &mut Vec<String>) {
+            match value {
+                toml::Value::Table(table) => {
+                    for (key, value) in table {
+                        keys(&format!("{}{}.", prefix, key), value, out);
+                    }
+                }
+                _ => out.push(prefix.trim_end_matches('.').to_string()),
+            }
+        }
+        let value = toml::Value::try_from(&config).unwrap();
+        let mut fields = Vec::new();
+        for section in ["holiday_data", "cache"] {
+            keys(&format!("{}.", section), &value[section], &mut fields);
+        }
+        let mut env_keys: Vec<String> = ENV_KEYS.iter().map(|(key, _)| key.to_string()).collect();
+        fields.sort();
+        env_keys.sort();
+        assert_eq!(env_keys, fields);
+    }
+
+    #[test]
+    fn test_overlays_are_relative_to_the_config_file() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[holiday_data]\noverlays = [\"./company.toml\", \"extra/local.csv\", \"/abs/global.csv\"]\n",
+        )
+        .unwrap();
+        let expected = [
+            dir.path()
+                .join("company.toml")
+                .to_string_lossy()
+                .into_owned(),
+            dir.path()
+                .join("extra/local.csv")
+                .to_string_lossy()
+                .into_owned(),
+            "/abs/global.csv".to_string(),
+        ];
+
+        let config = Config::builder()
+            .with_file(&path)
+            .build_with(|_| None)
+            .unwrap();
+        assert_eq!(config.holiday_data.overlays, expected);
+
+        // Paths from other layers stay relative to the current directory
+        let config = Config::builder()
+            .with_file(&path)
+            .with_overlays(["cwd.toml"])
+            .build_with(|_| None)
+            .unwrap();
+        assert_eq!(config.holiday_data.overlays, ["cwd.toml"]);
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[holiday_data]\nsource_url = \"file.csv\"\ncache_file = \"file.json\"\n\n[cache]\nmax_age_hours = 1\nstrategy = \"TimeBased\"\n",
+        )
+        .unwrap();
+        let vars: HashMap<&str, &str> = [
+            ("HOLIDAYS_JP_HOLIDAY_DATA_CACHE_FILE", "env.json"),
+            ("HOLIDAYS_JP_CACHE_MAX_AGE_HOURS", "2"),
+            ("HOLIDAYS_JP_CACHE_SANITY_UNIQUE_DATES", "off"),
+            ("HOLIDAYS_JP_HOLIDAY_DATA_HTTP_PROXY", "http://proxy:8080"),
+        ]
+        .into();
+        let var = |name: &str| vars.get(name).map(|value| value.to_string());
+
+        let config = Config::builder()
+            .with_file(&path)
+            .with_max_age_hours(3)
+            .with_refresh_error_policy(RefreshErrorPolicy::Fail)
+            .build_with(var)
+            .unwrap();
+        // Explicit value > env > file > default
+        assert_eq!(config.cache.max_age_hours, 3);
+        assert_eq!(config.cache.on_refresh_error, RefreshErrorPolicy::Fail);
+        assert_eq!(config.holiday_data.cache_file, "env.json");
+        assert_eq!(config.holiday_data.source_url, "file.csv");
+        assert!(matches!(config.cache.strategy, CacheStrategy::TimeBased));
+        assert_eq!(config.cache.etag_check_interval_hours, 24);
+        assert!(!config.cache.sanity.unique_dates);
+        assert_eq!(
+            config.holiday_data.http.proxy.as_deref(),
+            Some("http://proxy:8080")
+        );
+
+        let config = Config::builder()
+            .with_file(&path)
+            .with_env(false)
+            .build_with(var)
+            .unwrap();
+        assert_eq!(config.holiday_data.cache_file, "file.json");
+
+        let bad =
+            |name: &str| (name == "HOLIDAYS_JP_CACHE_MAX_AGE_HOURS").then(|| "soon".to_string());
+        let error = Config::builder()
+            .without_file()
+            .build_with(bad)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("HOLIDAYS_JP_CACHE_MAX_AGE_HOURS"));
+        assert!(Config::builder()
+            .with_file(dir.path().join("missing.toml"))
+            .build_with(|_| None)
+            .is_err());
+
+        let scalar = dir.path().join("scalar.toml");
+        std::fs::write(&scalar, "holiday_data = 1\n").unwrap();
+        let error = Config::builder()
+            .with_file(&scalar)
+            .with_source_url("builder.csv")
+            .build_with(|_| None)
+            .unwrap_err();
+        assert!(error.to_string().contains("not a table"));
+    }
+
+    #[test]
+    fn test_builder_setters() {
+        let config = Config::builder()
+            .without_file()
+            .with_source_url("data.csv")
+            .with_cache_file("cache.json")
+            .with_overlays(["company.toml"])
+            .with_cache_strategy(CacheStrategy::NeverRefresh)
+            .with_max_age_hours(1)
+            .with_etag_check_interval_hours(2)
+            .with_force_refresh_on_startup(true)
+            .with_cache_backend(CacheBackend::Memory)
+            .with_refresh_error_policy(RefreshErrorPolicy::Fail)
+            .build_with(|_| None)
+            .unwrap();
+        assert_eq!(config.holiday_data.source_url, "data.csv");
+        assert_eq!(config.holiday_data.cache_file, "cache.json");
+        assert_eq!(config.holiday_data.overlays, ["company.toml"]);
+        assert!(matches!(config.cache.strategy, CacheStrategy::NeverRefresh));
+        assert_eq!(config.cache.max_age_hours, 1);
+        assert_eq!(config.cache.etag_check_interval_hours, 2);
+        assert!(config.cache.force_refresh_on_startup);
+        assert_eq!(config.cache.backend, CacheBackend::Memory);
+        assert_eq!(config.cache.on_refresh_error, RefreshErrorPolicy::Fail);
     }
 }
@@ -6,6 +6,19 @@
 /// Configuration file name
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 
+/// Configuration file searched in the current directory; named after the tool, unlike
+/// `CONFIG_FILE_NAME`, so another program's `config.toml` is not picked up
+pub const LOCAL_CONFIG_FILE_NAME: &str = "holidays_jp.toml";
+
+/// Directory under the XDG config and cache directories
+pub const APP_DIR_NAME: &str = "holidays_jp";
+
+/// Prefix of the environment variables overriding config values, e.g. `HOLIDAYS_JP_CACHE_STRATEGY`
+pub const ENV_PREFIX: &str = "HOLIDAYS_JP_";
+
+/// Environment variable naming the config file, like `--config`
+pub const CONFIG_PATH_ENV: &str = "HOLIDAYS_JP_CONFIG";
+
 /// Supported date formats for flexible parsing (technical implementation detail)
 pub const SUPPORTED_DATE_FORMATS: &[&str] = &[
     "%Y%m%d",       // 20230101
@@ -28,4 +41,14 @@ pub const CACHE_STRATEGY_OPTIONS: &[&str] = &[
 
 /// Default configuration values (used by Config::default() and when creating initial config.toml)
 pub const DEFAULT_SOURCE_URL: &str = "https://www8.cao.go.jp/chosei/shukujitsu/syukujitsu.csv";
-pub const DEFAULT_CACHE_FILE: &str = "./data/holidays.json";
+/// Cache file name under `$XDG_CACHE_HOME/holidays_jp`
+pub const DEFAULT_CACHE_FILE_NAME: &str = "holidays.json";
+/// Cache file used when neither `XDG_CACHE_HOME` nor `HOME` is set
+pub const FALLBACK_CACHE_FILE: &str = "./data/holidays.json";
+
+/// Default HTTP settings for downloading the data (see `HttpConfig`)
+pub const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_HTTP_CHECK_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_HTTP_RETRIES: u32 = 2;
+pub const DEFAULT_HTTP_BACKOFF_MS: u64 = 500;
+pub const DEFAULT_HTTP_MAX_BACKOFF_MS: u64 = 10_000;
@@ -0,0 +1,139 @@
+//! Differences between two versions of the holiday data.
+//!
+//! `HolidayCache` compares the cached date -> name map with downloaded data, so
+//! `holidays_jp update` can show what a refresh changed (or would change).
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// A single change from the old data to the new
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HolidayChange {
+    /// A holiday only in the new data
+    Added { date: NaiveDate, name: String },
+    /// A holiday only in the old data
+    Removed { date: NaiveDate, name: String },
+    /// A holiday in both with different names
+    Renamed {
+        date: NaiveDate,
+        old_name: String,
+        new_name: String,
+    },
+}
+
+impl HolidayChange {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            HolidayChange::Added { date, .. }
+            | HolidayChange::Removed { date, .. }
+            | HolidayChange::Renamed { date, .. } => *date,
+        }
+    }
+}
+
+/// Changes between two versions of the data, ordered by date
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HolidayDiff {
+    pub changes: Vec<HolidayChange>,
+}
+
+impl HolidayDiff {
+    /// Compare `old` with `new`
+    pub fn between(old: &HashMap<NaiveDate, String>, new: &HashMap<NaiveDate, String>) -> Self {
+        let dates: BTreeSet<&NaiveDate> = old.keys().chain(new.keys()).collect();
+        let changes = dates
+            .into_iter()
+            .filter_map(|&date| match (old.get(&date), new.get(&date)) {
+                (None, Some(name)) => Some(HolidayChange::Added {
+                    date,
+                    name: name.clone(),
+                }),
+                (Some(name), None) => Some(HolidayChange::Removed {
+                    date,
+                    name: name.clone(),
+                }),
+                (Some(old_name), Some(new_name)) if old_name != new_name => {
+                    Some(HolidayChange::Renamed {
+                        date,
+                        old_name: old_name.clone(),
+                        new_name: new_name.clone(),
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn added(&self) -> usize {
+        self.count(|change| matches!(change, HolidayChange::Added { .. }))
+    }
+
+    pub fn removed(&self) -> usize {
+        self.count(|change| matches!(change, HolidayChange::Removed { .. }))
+    }
+
+    pub fn renamed(&self) -> usize {
+        self.count(|change| matches!(change, HolidayChange::Renamed { .. }))
+    }
+
+    fn count(&self, predicate: impl Fn(&HolidayChange) -> bool) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| predicate(change))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_diff_between_versions() {
+        let old: HashMap<NaiveDate, String> = [
+            (date(2024, 1, 1), "元日".to_string()),
+            (date(2024, 11, 4), "休日".to_string()),
+            (date(2024, 7, 15), "海の日".to_string()),
+        ]
+        .into();
+        let new: HashMap<NaiveDate, String> = [
+            (date(2024, 1, 1), "元日".to_string()),
+            (date(2024, 11, 4), "振替休日".to_string()),
+            (date(2025, 1, 1), "元日".to_string()),
+        ]
+        .into();
+
+        let diff = HolidayDiff::between(&old, &new);
+        assert_eq!(
+            diff.changes,
+            vec![
+                HolidayChange::Removed {
+                    date: date(2024, 7, 15),
+                    name: "海の日".to_string()
+                },
+                HolidayChange::Renamed {
+                    date: date(2024, 11, 4),
+                    old_name: "休日".to_string(),
+                    new_name: "振替休日".to_string()
+                },
+                HolidayChange::Added {
+                    date: date(2025, 1, 1),
+                    name: "元日".to_string()
+                },
+            ]
+        );
+        assert_eq!((diff.added(), diff.removed(), diff.renamed()), (1, 1, 1));
+        assert!(HolidayDiff::between(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! Holiday data compiled into the binary (`embedded` feature).
+//!
+//! `data/syukujitsu.csv` is a snapshot of the Cabinet Office CSV, so the service
+//! can start without any file or network access. Replace the file and
+//! `SNAPSHOT_DATE` together when refreshing it.
+
+use crate::cache::HolidayCache;
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// The embedded CSV, in the Cabinet Office format (Shift_JIS or UTF-8)
+pub const SNAPSHOT_CSV: &[u8] = include_bytes!("../data/syukujitsu.csv");
+
+/// Date the embedded CSV was taken, as YYYY-MM-DD
+pub const SNAPSHOT_DATE: &str = "2026-10-16";
+
+pub fn snapshot_date() -> NaiveDate {
+    NaiveDate::parse_from_str(SNAPSHOT_DATE, "%Y-%m-%d").expect("SNAPSHOT_DATE is a valid date")
+}
+
+/// Parse the embedded CSV into a date -> name map
+pub fn holiday_names() -> Result<HashMap<NaiveDate, String>> {
+    HolidayCache::parse_csv(&HolidayCache::decode_csv(SNAPSHOT_CSV))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify;
+
+    #[test]
+    fn test_snapshot_matches_rules() {
+        let names = holiday_names().unwrap();
+        assert_eq!(
+            names.get(&NaiveDate::from_ymd_opt(2019, 5, 1).unwrap()),
+            Some(&"休日（祝日扱い）".to_string())
+        );
+        assert!(verify::verify_names(&names).is_clean());
+        assert!(snapshot_date() > NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    }
+}
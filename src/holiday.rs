@@ -0,0 +1,165 @@
+//! Typed holiday model shared by the cache, the service and the CLI.
+
+use crate::calendar::Calendar;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Name the Cabinet Office CSV uses for both 振替休日 and 国民の休日
+pub const GENERIC_HOLIDAY_NAME: &str = "休日";
+
+/// Category of a holiday
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HolidayKind {
+    /// 国民の祝日 - a named national holiday
+    National,
+    /// 振替休日 - substitute holiday for a national holiday falling on Sunday
+    Substitute,
+    /// 国民の休日 - a day sandwiched between two national holidays
+    Citizens,
+    /// A one-off holiday designated by a special law, e.g. for an imperial ceremony
+    Special,
+    /// A holiday added by an overlay file, e.g. a company holiday
+    Custom,
+    /// A closed day of a non-national calendar, e.g. the banks' year-end closure
+    Closure,
+}
+
+/// Where a holiday came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HolidayOrigin {
+    /// The configured holiday data source (the Cabinet Office CSV by default)
+    Official,
+    /// Computed by the rule engine for a year the data does not cover
+    Computed,
+    /// An overlay file listed in `holiday_data.overlays`
+    Overlay { path: String },
+    /// Added by a calendar other than the national one
+    Calendar { calendar: Calendar },
+}
+
+impl fmt::Display for HolidayOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HolidayOrigin::Official => write!(f, "official"),
+            HolidayOrigin::Computed => write!(f, "computed"),
+            HolidayOrigin::Overlay { path } => write!(f, "overlay: {}", path),
+            HolidayOrigin::Calendar { calendar } => write!(f, "calendar: {}", calendar),
+        }
+    }
+}
+
+/// A single holiday
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+    pub kind: HolidayKind,
+    pub origin: HolidayOrigin,
+}
+
+impl Holiday {
+    /// A holiday from the official data
+    pub fn new(date: NaiveDate, name: impl Into<String>, kind: HolidayKind) -> Self {
+        Self {
+            date,
+            name: name.into(),
+            kind,
+            origin: HolidayOrigin::Official,
+        }
+    }
+
+    pub fn with_origin(mut self, origin: HolidayOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+}
+
+/// Result of looking up a single date
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HolidayLookup {
+    pub date: NaiveDate,
+    pub holiday: Option<Holiday>,
+    /// The answer comes from a cache that could not be refreshed (see `HolidayService::staleness`)
+    pub stale: bool,
+}
+
+impl HolidayLookup {
+    pub fn is_holiday(&self) -> bool {
+        self.holiday.is_some()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.holiday.as_ref().map(|h| h.name.as_str())
+    }
+
+    pub fn kind(&self) -> Option<HolidayKind> {
+        self.holiday.as_ref().map(|h| h.kind)
+    }
+
+    pub fn origin(&self) -> Option<&HolidayOrigin> {
+        self.holiday.as_ref().map(|h| &h.origin)
+    }
+}
+
+/// Build typed holidays from a date -> name map as stored in the CSV and the cache.
+///
+/// The CSV names both substitute holidays and citizens' holidays "休日", so the kind
+/// is inferred from the neighbouring days: a "休日" sandwiched between two named
+/// holidays is a citizens' holiday, anything else is a substitute holiday.
+pub fn build_holidays(names: &HashMap<NaiveDate, String>) -> HashMap<NaiveDate, Holiday> {
+    let is_named = |date: NaiveDate| {
+        names
+            .get(&date)
+            .is_some_and(|name| name != GENERIC_HOLIDAY_NAME)
+    };
+
+    names
+        .iter()
+        .map(|(&date, name)| {
+            let kind = if crate::rules::special_holiday_name(date).is_some() {
+                HolidayKind::Special
+            } else if name != GENERIC_HOLIDAY_NAME {
+                HolidayKind::National
+            } else if is_named(date - Duration::days(1)) && is_named(date + Duration::days(1)) {
+                HolidayKind::Citizens
+            } else {
+                HolidayKind::Substitute
+            };
+            (date, Holiday::new(date, name.clone(), kind))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_build_holidays_infers_kind() {
+        let names: HashMap<NaiveDate, String> = [
+            (date(2019, 4, 29), "昭和の日"),
+            (date(2019, 4, 30), "休日"),
+            (date(2019, 5, 1), "休日（祝日扱い）"),
+            (date(2023, 1, 1), "元日"),
+            (date(2023, 1, 2), "休日"),
+        ]
+        .into_iter()
+        .map(|(d, n)| (d, n.to_string()))
+        .collect();
+
+        let holidays = build_holidays(&names);
+
+        assert_eq!(holidays[&date(2019, 4, 29)].kind, HolidayKind::National);
+        assert_eq!(holidays[&date(2019, 4, 30)].kind, HolidayKind::Citizens);
+        assert_eq!(holidays[&date(2019, 5, 1)].kind, HolidayKind::Special);
+        assert_eq!(holidays[&date(2023, 1, 2)].kind, HolidayKind::Substitute);
+    }
+}
@@ -1,80 +1,659 @@
-use crate::cache::HolidayCache;
+use crate::business_day::{Weekend, WorkCalendar};
+use crate::cache::{CachedHolidays, HolidayCache, Staleness};
+use crate::calendar::Calendar;
 use crate::config::Config;
 use crate::constants::*;
+use crate::diff::HolidayDiff;
+#[cfg(feature = "embedded")]
+use crate::embedded;
+use crate::holiday::{build_holidays, Holiday, HolidayLookup};
+use crate::index::HolidayIndex;
+use crate::overlay::Overlay;
+use crate::parse::ParseReport;
+use crate::rules;
+#[cfg(feature = "embedded")]
+use crate::source::EmbeddedSource;
+use crate::source::{FileSource, HolidaySource};
+use crate::store::CacheStore;
+use crate::verify::{self, VerifyReport};
 use anyhow::Result;
-use chrono::{Local, NaiveDate};
-use std::collections::HashMap;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Give up on business-day arithmetic after this many consecutive non-business days
+const MAX_DAYS_WITHOUT_BUSINESS_DAY: u32 = 366;
+
+/// Stop searching for the next or previous holiday after this many years without one
+const MAX_YEARS_WITHOUT_HOLIDAY: u32 = 10;
+
+/// Most holidays `next_n_holidays` and `previous_n_holidays` return, about 60 years' worth;
+/// larger counts are capped, as each further year is computed
+pub const MAX_NEAREST_HOLIDAYS: usize = 1000;
+
+/// Years after the data (or the current year) that are precomputed into the index
+const INDEXED_YEARS_AHEAD: i32 = 30;
+
+/// Where the loaded holiday data came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    /// The cache file, downloaded from `holiday_data.source_url`
+    Cache,
+    /// A Cabinet Office CSV file given to `initialize_from_csv_file`
+    File { path: String },
+    /// The snapshot compiled in with the `embedded` feature
+    Embedded { snapshot_date: NaiveDate },
+    /// A source given to `initialize_from_source`
+    Source { description: String },
+}
 
 pub struct HolidayService {
     cache: HolidayCache,
-    holidays: Option<HashMap<String, String>>,
+    holidays: Option<BTreeMap<NaiveDate, Holiday>>,
+    data_source: Option<DataSource>,
+    /// Set when the cache was served because its refresh failed
+    stale: Option<Staleness>,
+    /// Why the cache was replaced, when it could not be parsed
+    discarded_cache: Option<String>,
+    /// How the CSV was parsed when the data was just downloaded or read from a file
+    parse_report: Option<ParseReport>,
+    /// What the last `update` changed in the cache
+    diff: Option<HolidayDiff>,
+    /// First and last year covered by the loaded CSV data; other years are computed by `rules`
+    data_years: Option<(i32, i32)>,
+    /// Data, computed years, calendar closures and overlay merged for fast queries
+    index: Option<HolidayIndex>,
+    calendar: Calendar,
+    work_calendar: WorkCalendar,
+    overlay_files: Vec<String>,
+    overlay: Overlay,
 }
 
 impl HolidayService {
     pub fn new(config: Config) -> Self {
         Self {
+            work_calendar: config.work_calendar.clone(),
+            overlay_files: config.holiday_data.overlays.clone(),
             cache: HolidayCache::new(config),
             holidays: None,
+            data_source: None,
+            stale: None,
+            discarded_cache: None,
+            parse_report: None,
+            diff: None,
+            data_years: None,
+            index: None,
+            calendar: Calendar::default(),
+            overlay: Overlay::default(),
         }
     }
 
+    /// Add the closed days of a banking or exchange calendar to the national holidays
+    pub fn with_calendar(mut self, calendar: Calendar) -> Self {
+        self.calendar = calendar;
+        self.rebuild_index();
+        self
+    }
+
+    pub fn calendar(&self) -> Calendar {
+        self.calendar
+    }
+
+    /// Layer additional holidays on top of the data, replacing any overlay loaded
+    /// from `holiday_data.overlays` at initialization
+    pub fn with_overlay(mut self, overlay: Overlay) -> Self {
+        self.overlay_files.clear();
+        self.overlay = overlay;
+        self.rebuild_index();
+        self
+    }
+
+    /// Use a different weekend definition for business-day queries (default: Saturday and Sunday)
+    pub fn with_weekend(mut self, weekend: Weekend) -> Self {
+        self.work_calendar.weekend = weekend;
+        self
+    }
+
+    /// Use a different office calendar for business-day queries (default: `config.work_calendar`)
+    pub fn with_work_calendar(mut self, work_calendar: WorkCalendar) -> Self {
+        self.work_calendar = work_calendar;
+        self
+    }
+
+    pub fn weekend(&self) -> Weekend {
+        self.work_calendar.weekend
+    }
+
+    pub fn work_calendar(&self) -> &WorkCalendar {
+        &self.work_calendar
+    }
+
+    /// Refresh the cache from `source` instead of `holiday_data.source_url`
+    pub fn with_source(mut self, source: impl HolidaySource + 'static) -> Self {
+        self.cache = self.cache.with_source(Arc::new(source));
+        self
+    }
+
+    /// Keep the cache in `store` instead of the one selected by `cache.backend`
+    pub fn with_store(mut self, store: impl CacheStore + 'static) -> Self {
+        self.cache = self.cache.with_store(Arc::new(store));
+        self
+    }
+
+    pub fn cache(&self) -> &HolidayCache {
+        &self.cache
+    }
+
+    /// Load the cache file, downloading or refreshing it according to `config.cache`
+    #[cfg(feature = "fetch")]
     pub async fn initialize(&mut self) -> Result<()> {
-        self.holidays = Some(self.cache.get_holidays().await?);
+        let cached = self.cache.get_holidays().await?;
+        self.set_cached_holidays(cached)
+    }
+
+    /// Like `initialize`, but blocks the current thread while the source is fetched
+    pub fn initialize_blocking(&mut self) -> Result<()> {
+        let cached = self.cache.load_holidays()?;
+        self.set_cached_holidays(cached)
+    }
+
+    /// Download the data again, whatever `config.cache` says, and reload it; on failure
+    /// the cache and the loaded data are kept
+    #[cfg(feature = "fetch")]
+    pub async fn update(&mut self) -> Result<()> {
+        let cache = self.cache.clone();
+        let cached = tokio::task::spawn_blocking(move || cache.refresh_holidays()).await??;
+        self.set_cached_holidays(cached)
+    }
+
+    /// Like `update`, but blocks the current thread while the source is fetched
+    pub fn update_blocking(&mut self) -> Result<()> {
+        let cached = self.cache.refresh_holidays()?;
+        self.set_cached_holidays(cached)
+    }
+
+    /// Download the latest data and report what `update` would change, without
+    /// writing the cache or changing the loaded data
+    #[cfg(feature = "fetch")]
+    pub async fn preview_update(&self) -> Result<CachedHolidays> {
+        let cache = self.cache.clone();
+        tokio::task::spawn_blocking(move || cache.preview_refresh()).await?
+    }
+
+    /// Like `preview_update`, but blocks the current thread while the source is fetched
+    pub fn preview_update_blocking(&self) -> Result<CachedHolidays> {
+        self.cache.preview_refresh()
+    }
+
+    /// Initialize from the cache file as is, without network access
+    pub fn initialize_from_cache(&mut self) -> Result<()> {
+        let holidays = self.cache.get_cached_holidays()?;
+        self.load_overlays()?;
+        self.set_holidays(holidays);
+        self.data_source = Some(DataSource::Cache);
         Ok(())
     }
 
-    pub fn get_holiday(&self, date: &str) -> Result<(bool, Option<String>)> {
-        let holidays = self
-            .holidays
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Holiday service not initialized"))?;
+    /// Initialize from a CSV file in the Cabinet Office format (Shift_JIS or UTF-8)
+    pub fn initialize_from_csv_file(&mut self, path: &str) -> Result<()> {
+        self.load_source(&FileSource::new(path))?;
+        self.data_source = Some(DataSource::File {
+            path: path.to_string(),
+        });
+        Ok(())
+    }
 
-        let parsed_date = self.parse_date_flexible(date)?;
-        let formatted_date = parsed_date.format("%Y-%m-%d").to_string();
+    /// Initialize from `source` directly, bypassing the cache file
+    pub fn initialize_from_source(&mut self, source: &dyn HolidaySource) -> Result<()> {
+        self.load_source(source)?;
+        self.data_source = Some(DataSource::Source {
+            description: source.describe(),
+        });
+        Ok(())
+    }
 
-        if let Some(holiday_name) = holidays.get(&formatted_date) {
-            Ok((true, Some(holiday_name.clone())))
-        } else {
-            Ok((false, None))
+    /// Initialize from the embedded snapshot only, without any file or network access
+    /// (overlay files listed in the config are still read)
+    #[cfg(feature = "embedded")]
+    pub fn initialize_embedded(&mut self) -> Result<()> {
+        self.load_source(&EmbeddedSource)?;
+        self.data_source = Some(DataSource::Embedded {
+            snapshot_date: embedded::snapshot_date(),
+        });
+        Ok(())
+    }
+
+    /// Initialize without network access: the cache file is used when it is newer
+    /// than the embedded snapshot, otherwise the snapshot
+    #[cfg(feature = "embedded")]
+    pub fn initialize_offline(&mut self) -> Result<()> {
+        let snapshot_date = embedded::snapshot_date();
+        // An unreadable cache is no worse than a missing one here
+        let cached = self
+            .cache
+            .load_cached()
+            .ok()
+            .flatten()
+            .filter(|cache| cache.metadata.last_updated.date_naive() > snapshot_date);
+        match cached {
+            Some(cache) => {
+                self.load_overlays()?;
+                self.set_holidays(build_holidays(&cache.holidays));
+                self.data_source = Some(DataSource::Cache);
+                Ok(())
+            }
+            None => self.initialize_embedded(),
         }
     }
 
-    pub fn get_holidays_in_range(
-        &self,
-        start_date: &str,
-        end_date: &str,
-    ) -> Result<Vec<(String, String)>> {
-        let holidays = self
-            .holidays
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Holiday service not initialized"))?;
+    /// Where the loaded data came from; `None` before initialization
+    pub fn data_source(&self) -> Option<&DataSource> {
+        self.data_source.as_ref()
+    }
+
+    /// Set when the loaded cache is out of date because refreshing it failed
+    /// (`cache.on_refresh_error = "ServeStale"`)
+    pub fn staleness(&self) -> Option<&Staleness> {
+        self.stale.as_ref()
+    }
+
+    /// Set when the cache could not be parsed and was downloaded again, with the reason
+    pub fn discarded_cache(&self) -> Option<&str> {
+        self.discarded_cache.as_deref()
+    }
+
+    /// How the CSV was parsed, when the data was downloaded or read from a CSV file
+    /// by this initialization; `None` when it came from the cache
+    pub fn parse_report(&self) -> Option<&ParseReport> {
+        self.parse_report.as_ref()
+    }
+
+    /// What the last `update` changed in the cache; `None` when the data was not downloaded
+    pub fn update_diff(&self) -> Option<&HolidayDiff> {
+        self.diff.as_ref()
+    }
+
+    fn set_cached_holidays(&mut self, cached: CachedHolidays) -> Result<()> {
+        self.load_overlays()?;
+        self.set_holidays(cached.holidays);
+        self.data_source = Some(DataSource::Cache);
+        self.stale = cached.stale;
+        self.discarded_cache = cached.discarded;
+        self.parse_report = cached.report;
+        self.diff = cached.diff;
+        Ok(())
+    }
+
+    fn load_source(&mut self, source: &dyn HolidaySource) -> Result<()> {
+        let data = source.fetch()?;
+        self.load_overlays()?;
+        self.set_holidays(build_holidays(&data.holidays));
+        self.parse_report = data.report;
+        Ok(())
+    }
 
+    fn load_overlays(&mut self) -> Result<()> {
+        if !self.overlay_files.is_empty() {
+            self.overlay = Overlay::load_all(&self.overlay_files)?;
+        }
+        Ok(())
+    }
+
+    fn set_holidays(&mut self, holidays: HashMap<NaiveDate, Holiday>) {
+        self.stale = None;
+        self.discarded_cache = None;
+        self.parse_report = None;
+        self.diff = None;
+        let years = holidays.keys().map(|date| date.year());
+        self.data_years = years.clone().min().zip(years.max());
+        self.holidays = Some(holidays.into_iter().collect());
+        self.rebuild_index();
+    }
+
+    /// Precompute every holiday from the Act (or the data, if earlier) until
+    /// `INDEXED_YEARS_AHEAD` years after the data or the current year
+    fn rebuild_index(&mut self) {
+        if self.holidays.is_none() {
+            return;
+        }
+        let act_year = rules::ACT_EFFECTIVE.0;
+        let (data_first, data_last) = self.data_years.unwrap_or((act_year, act_year));
+        let first = NaiveDate::from_ymd_opt(data_first.min(act_year), 1, 1);
+        let last = NaiveDate::from_ymd_opt(
+            data_last.max(Local::now().year()) + INDEXED_YEARS_AHEAD,
+            12,
+            31,
+        );
+        if let (Some(first), Some(last)) = (first, last) {
+            let holidays = self.compute_holidays_between(first, last);
+            self.index = Some(HolidayIndex::new(first, last, holidays));
+        }
+    }
+
+    /// Look up a date given as a string in any of the `SUPPORTED_DATE_FORMATS`
+    pub fn get_holiday(&self, date: &str) -> Result<HolidayLookup> {
+        self.lookup(self.parse_date_flexible(date)?)
+    }
+
+    /// List holidays between two dates given as strings, inclusive
+    pub fn get_holidays_in_range(&self, start_date: &str, end_date: &str) -> Result<Vec<Holiday>> {
         let start = self.parse_date_flexible(start_date)?;
         let end = self.parse_date_flexible(end_date)?;
+        self.holidays_between(start, end)
+    }
 
-        if start > end {
-            return Err(anyhow::anyhow!(
-                "Start date must be before or equal to end date"
-            ));
+    pub fn lookup(&self, date: NaiveDate) -> Result<HolidayLookup> {
+        let index = self.loaded_index()?;
+        let holiday = if index.covers(date) {
+            index.get(date).cloned()
+        } else {
+            self.compute_holidays_between(date, date).pop()
+        };
+        Ok(HolidayLookup {
+            date,
+            holiday,
+            stale: self.stale.is_some(),
+        })
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> Result<bool> {
+        Ok(self.lookup(date)?.is_holiday())
+    }
+
+    pub fn holiday_name(&self, date: NaiveDate) -> Result<Option<String>> {
+        Ok(self.lookup(date)?.holiday.map(|holiday| holiday.name))
+    }
+
+    /// List holidays between `start` and `end`, inclusive, in date order
+    pub fn holidays_between(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<Holiday>> {
+        let index = self.loaded_index()?;
+        check_range(start, end)?;
+
+        let mut result = Vec::new();
+        if start < index.start() {
+            result.extend(self.compute_holidays_between(start, end.min(day_before(index.start()))));
         }
+        result.extend_from_slice(index.range(start, end));
+        if end > index.end() {
+            result.extend(self.compute_holidays_between(start.max(day_after(index.end())), end));
+        }
+        Ok(result)
+    }
+
+    /// Number of holidays between `start` and `end`, inclusive
+    pub fn count_holidays(&self, start: NaiveDate, end: NaiveDate) -> Result<usize> {
+        let index = self.loaded_index()?;
+        check_range(start, end)?;
+
+        let mut count = index.count(start, end);
+        if start < index.start() {
+            count += self
+                .compute_holidays_between(start, end.min(day_before(index.start())))
+                .len();
+        }
+        if end > index.end() {
+            count += self
+                .compute_holidays_between(start.max(day_after(index.end())), end)
+                .len();
+        }
+        Ok(count)
+    }
+
+    /// Holidays from the data, the rules, the calendar and the overlay, bypassing the index
+    fn compute_holidays_between(&self, start: NaiveDate, end: NaiveDate) -> Vec<Holiday> {
+        let mut result: Vec<Holiday> = self
+            .holidays
+            .iter()
+            .flat_map(|holidays| {
+                holidays
+                    .range(start..=end)
+                    .map(|(_, holiday)| holiday.clone())
+            })
+            .collect();
+
+        // Years outside the CSV coverage are computed as a whole
+        for year in start.year()..=end.year() {
+            if !self.is_covered_by_data(year) {
+                result.extend(
+                    rules::holidays_in_year(year)
+                        .into_iter()
+                        .filter(|holiday| holiday.date >= start && holiday.date <= end),
+                );
+            }
+        }
+
+        let closures = self.calendar.closures_between(start, end);
+        if !closures.is_empty() {
+            let national: HashSet<NaiveDate> = result.iter().map(|holiday| holiday.date).collect();
+            result.extend(
+                closures
+                    .into_iter()
+                    .filter(|closure| !national.contains(&closure.date)),
+            );
+        }
+
+        result.sort_by_key(|holiday| holiday.date);
+        self.overlay.apply_range(start, end, result)
+    }
+
+    /// The first holiday after `from`, not counting `from` itself
+    pub fn next_holiday(&self, from: NaiveDate) -> Result<Option<Holiday>> {
+        Ok(self.nearest_holidays(from, 1, true)?.pop())
+    }
+
+    /// The last holiday before `from`, not counting `from` itself
+    pub fn previous_holiday(&self, from: NaiveDate) -> Result<Option<Holiday>> {
+        Ok(self.nearest_holidays(from, 1, false)?.pop())
+    }
+
+    /// Up to `n` (at most `MAX_NEAREST_HOLIDAYS`) holidays after `from`, in date order
+    pub fn next_n_holidays(&self, from: NaiveDate, n: usize) -> Result<Vec<Holiday>> {
+        self.nearest_holidays(from, n, true)
+    }
+
+    /// Up to `n` (at most `MAX_NEAREST_HOLIDAYS`) holidays before `from`, nearest first
+    pub fn previous_n_holidays(&self, from: NaiveDate, n: usize) -> Result<Vec<Holiday>> {
+        self.nearest_holidays(from, n, false)
+    }
+
+    /// Holidays strictly after (`forward`) or before `from`, nearest first
+    fn nearest_holidays(&self, from: NaiveDate, n: usize, forward: bool) -> Result<Vec<Holiday>> {
+        let index = self.loaded_index()?;
+        let n = n.min(MAX_NEAREST_HOLIDAYS);
+        let bound = if forward {
+            from.succ_opt()
+        } else {
+            from.pred_opt()
+        };
+        let bound = match bound {
+            Some(bound) => bound,
+            None => return Ok(Vec::new()),
+        };
 
         let mut result = Vec::new();
-        let mut current = start;
+        if forward {
+            if bound < index.start() {
+                result.extend(
+                    self.compute_holidays_between(bound, day_before(index.start()))
+                        .into_iter()
+                        .take(n),
+                );
+            }
+            result.extend(index.since(bound).iter().take(n - result.len()).cloned());
+            self.extend_beyond_index(&mut result, n, bound.max(day_after(index.end())), true)?;
+        } else {
+            if bound > index.end() {
+                result.extend(
+                    self.compute_holidays_between(day_after(index.end()), bound)
+                        .into_iter()
+                        .rev()
+                        .take(n),
+                );
+            }
+            result.extend(
+                index
+                    .until(bound)
+                    .iter()
+                    .rev()
+                    .take(n - result.len())
+                    .cloned(),
+            );
+            self.extend_beyond_index(&mut result, n, bound.min(day_before(index.start())), false)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Continue a nearest-holiday search past the index one year at a time, starting at `bound`
+    fn extend_beyond_index(
+        &self,
+        result: &mut Vec<Holiday>,
+        n: usize,
+        bound: NaiveDate,
+        forward: bool,
+    ) -> Result<()> {
+        let mut year = bound.year();
+        let mut years_without_holiday = 0;
+
+        while result.len() < n && years_without_holiday < MAX_YEARS_WITHOUT_HOLIDAY {
+            let (first, last) = match (
+                NaiveDate::from_ymd_opt(year, 1, 1),
+                NaiveDate::from_ymd_opt(year, 12, 31),
+            ) {
+                (Some(first), Some(last)) => (first, last),
+                _ => break,
+            };
+            let (start, end) = if forward {
+                (first.max(bound), last)
+            } else {
+                (first, last.min(bound))
+            };
+
+            let mut holidays = self.holidays_between(start, end)?;
+            if !forward {
+                holidays.reverse();
+            }
 
-        while current <= end {
-            let date_str = current.format("%Y-%m-%d").to_string();
-            if let Some(holiday_name) = holidays.get(&date_str) {
-                result.push((date_str, holiday_name.clone()));
+            if holidays.is_empty() {
+                years_without_holiday += 1;
+            } else {
+                years_without_holiday = 0;
             }
+            result.extend(holidays.into_iter().take(n - result.len()));
+            year += if forward { 1 } else { -1 };
+        }
+
+        Ok(())
+    }
+
+    /// True when `date` is neither a weekend day, a closed day of the work calendar
+    /// nor a holiday, or when the work calendar forces it to be a working day
+    pub fn is_business_day(&self, date: NaiveDate) -> Result<bool> {
+        if self.work_calendar.is_working_day_override(date) {
+            return Ok(true);
+        }
+        Ok(!self.work_calendar.is_closed(date) && !self.is_holiday(date)?)
+    }
+
+    /// The `n`-th business day after `date`; `date` itself is returned when `n` is zero
+    pub fn add_business_days(&self, date: NaiveDate, n: u32) -> Result<NaiveDate> {
+        self.step_business_days(date, n, Duration::days(1))
+    }
+
+    /// The `n`-th business day before `date`; `date` itself is returned when `n` is zero
+    pub fn sub_business_days(&self, date: NaiveDate, n: u32) -> Result<NaiveDate> {
+        self.step_business_days(date, n, Duration::days(-1))
+    }
+
+    /// Number of business days in `start..end` (start inclusive, end exclusive),
+    /// negative when `end` is before `start`
+    pub fn business_days_between(&self, start: NaiveDate, end: NaiveDate) -> Result<i64> {
+        let (from, to, sign) = if start <= end {
+            (start, end, 1)
+        } else {
+            (end, start, -1)
+        };
+
+        let mut count = 0;
+        let mut current = from;
+        while current < to {
+            if self.is_business_day(current)? {
+                count += 1;
+            }
+            current += Duration::days(1);
+        }
+
+        Ok(count * sign)
+    }
+
+    fn step_business_days(&self, date: NaiveDate, n: u32, step: Duration) -> Result<NaiveDate> {
+        if n > 0
+            && self.work_calendar.weekend.is_whole_week()
+            && self.work_calendar.working_days.is_empty()
+        {
+            return Err(anyhow::anyhow!(
+                "Weekend covers every day of the week; there are no business days"
+            ));
+        }
+
+        let mut current = date;
+        let mut remaining = n;
+        let mut days_without_business_day = 0;
+        while remaining > 0 {
             current = current
-                .succ_opt()
+                .checked_add_signed(step)
                 .ok_or_else(|| anyhow::anyhow!("Date overflow occurred"))?;
+            if self.is_business_day(current)? {
+                remaining -= 1;
+                days_without_business_day = 0;
+            } else {
+                days_without_business_day += 1;
+                if days_without_business_day > MAX_DAYS_WITHOUT_BUSINESS_DAY {
+                    return Err(anyhow::anyhow!(
+                        "No business day found within {} days of {}; check the work calendar",
+                        MAX_DAYS_WITHOUT_BUSINESS_DAY,
+                        current
+                    ));
+                }
+            }
         }
 
-        Ok(result)
+        Ok(current)
+    }
+
+    /// Compare the loaded data with the rule engine year by year
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let names = self
+            .loaded_holidays()?
+            .values()
+            .map(|holiday| (holiday.date, holiday.name.clone()))
+            .collect();
+        Ok(verify::verify_names(&names))
+    }
+
+    fn is_covered_by_data(&self, year: i32) -> bool {
+        self.data_years
+            .is_some_and(|(first, last)| (first..=last).contains(&year))
+    }
+
+    fn loaded_holidays(&self) -> Result<&BTreeMap<NaiveDate, Holiday>> {
+        self.holidays
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Holiday service not initialized"))
+    }
+
+    fn loaded_index(&self) -> Result<&HolidayIndex> {
+        self.index
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Holiday service not initialized"))
     }
 
     fn parse_date_flexible(&self, date_str: &str) -> Result<NaiveDate> {
+        Self::parse_date(date_str)
+    }
+
+    /// Parse a date string in any of the `SUPPORTED_DATE_FORMATS`
+    pub fn parse_date(date_str: &str) -> Result<NaiveDate> {
         for format in SUPPORTED_DATE_FORMATS {
             if let Ok(date) = NaiveDate::parse_from_str(date_str, format) {
                 return Ok(date);
@@ -92,6 +671,23 @@ impl HolidayService {
     }
 }
 
+fn check_range(start: NaiveDate, end: NaiveDate) -> Result<()> {
+    if start > end {
+        return Err(anyhow::anyhow!(
+            "Start date must be before or equal to end date"
+        ));
+    }
+    Ok(())
+}
+
+fn day_before(date: NaiveDate) -> NaiveDate {
+    date.pred_opt().unwrap_or(date)
+}
+
+fn day_after(date: NaiveDate) -> NaiveDate {
+    date.succ_opt().unwrap_or(date)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +701,532 @@ mod tests {
         assert!(service.holidays.is_none());
     }
 
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn service_with_holidays(entries: &[(NaiveDate, &str)]) -> HolidayService {
+        let mut service = HolidayService::new(Config::default());
+        let names = entries.iter().map(|(d, n)| (*d, n.to_string())).collect();
+        service.set_holidays(crate::holiday::build_holidays(&names));
+        service
+    }
+
+    #[test]
+    fn test_date_typed_queries() {
+        let service = service_with_holidays(&[
+            (date(2023, 1, 1), "元日"),
+            (date(2023, 1, 2), "休日"),
+            (date(2023, 1, 9), "成人の日"),
+        ]);
+
+        assert!(service.is_holiday(date(2023, 1, 1)).unwrap());
+        assert!(!service.is_holiday(date(2023, 1, 3)).unwrap());
+        assert_eq!(
+            service.holiday_name(date(2023, 1, 9)).unwrap().as_deref(),
+            Some("成人の日")
+        );
+
+        let holidays = service
+            .holidays_between(date(2023, 1, 1), date(2023, 1, 8))
+            .unwrap();
+        let dates: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+        assert_eq!(dates, vec![date(2023, 1, 1), date(2023, 1, 2)]);
+
+        assert_eq!(
+            service
+                .get_holidays_in_range("20230101", "2023/01/08")
+                .unwrap(),
+            holidays
+        );
+    }
+
+    #[test]
+    fn test_rules_answer_beyond_data() {
+        let service =
+            service_with_holidays(&[(date(2023, 1, 1), "元日"), (date(2023, 12, 31), "テスト")]);
+
+        // Within the data years, only the data is consulted
+        assert!(!service.is_holiday(date(2023, 1, 9)).unwrap());
+
+        // Later years fall back to the rule engine
+        assert_eq!(
+            service.holiday_name(date(2030, 1, 14)).unwrap().as_deref(),
+            Some("成人の日")
+        );
+
+        let holidays = service
+            .holidays_between(date(2023, 12, 1), date(2024, 1, 8))
+            .unwrap();
+        let dates: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+        assert_eq!(
+            dates,
+            vec![date(2023, 12, 31), date(2024, 1, 1), date(2024, 1, 8)]
+        );
+    }
+
+    #[test]
+    fn test_rules_answer_before_data() {
+        let service = service_with_holidays(&[(date(1955, 1, 1), "元日")]);
+
+        assert_eq!(
+            service.holiday_name(date(1950, 1, 15)).unwrap().as_deref(),
+            Some("成人の日")
+        );
+        assert!(!service.is_holiday(date(1947, 1, 1)).unwrap());
+
+        let holidays = service
+            .holidays_between(date(1954, 11, 1), date(1955, 1, 31))
+            .unwrap();
+        let dates: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+        assert_eq!(
+            dates,
+            vec![date(1954, 11, 3), date(1954, 11, 23), date(1955, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_business_days() {
+        // 2023-01-01 (Sun) 元日, 01-02 (Mon) 休日, 01-09 (Mon) 成人の日
+        let service = service_with_holidays(&[
+            (date(2023, 1, 1), "元日"),
+            (date(2023, 1, 2), "休日"),
+            (date(2023, 1, 9), "成人の日"),
+        ]);
+
+        assert!(!service.is_business_day(date(2023, 1, 2)).unwrap());
+        assert!(!service.is_business_day(date(2023, 1, 7)).unwrap());
+        assert!(service.is_business_day(date(2023, 1, 3)).unwrap());
+
+        assert_eq!(
+            service.add_business_days(date(2022, 12, 30), 1).unwrap(),
+            date(2023, 1, 3)
+        );
+        assert_eq!(
+            service.add_business_days(date(2023, 1, 5), 3).unwrap(),
+            date(2023, 1, 11)
+        );
+        assert_eq!(
+            service.sub_business_days(date(2023, 1, 10), 1).unwrap(),
+            date(2023, 1, 6)
+        );
+        assert_eq!(
+            service.add_business_days(date(2023, 1, 7), 0).unwrap(),
+            date(2023, 1, 7)
+        );
+
+        assert_eq!(
+            service
+                .business_days_between(date(2023, 1, 1), date(2023, 1, 16))
+                .unwrap(),
+            8
+        );
+        assert_eq!(
+            service
+                .business_days_between(date(2023, 1, 16), date(2023, 1, 1))
+                .unwrap(),
+            -8
+        );
+    }
+
+    #[test]
+    fn test_business_days_with_custom_weekend() {
+        use chrono::Weekday;
+
+        let service = service_with_holidays(&[(date(2023, 1, 1), "元日")])
+            .with_weekend(Weekend::new(&[Weekday::Sun]));
+
+        assert!(service.is_business_day(date(2023, 1, 7)).unwrap());
+        assert_eq!(
+            service.add_business_days(date(2023, 1, 6), 1).unwrap(),
+            date(2023, 1, 7)
+        );
+
+        let no_days = service.with_weekend(Weekend::new(&[
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ]));
+        assert!(no_days.add_business_days(date(2023, 1, 6), 1).is_err());
+    }
+
+    #[test]
+    fn test_business_days_with_work_calendar() {
+        let service =
+            service_with_holidays(&[(date(2023, 1, 1), "元日"), (date(2023, 1, 2), "休日")])
+                .with_work_calendar(
+                    toml::from_str(
+                        r#"
+                closed_days = ["12-29..01-03"]
+                working_days = ["2023-01-07"]
+                "#,
+                    )
+                    .unwrap(),
+                );
+
+        assert!(!service.is_business_day(date(2023, 1, 3)).unwrap());
+        assert!(service.is_business_day(date(2023, 1, 7)).unwrap());
+        assert_eq!(
+            service.add_business_days(date(2022, 12, 28), 1).unwrap(),
+            date(2023, 1, 4)
+        );
+        assert_eq!(
+            service.add_business_days(date(2023, 1, 6), 1).unwrap(),
+            date(2023, 1, 7)
+        );
+
+        let always_closed = service.with_work_calendar(WorkCalendar {
+            closed_days: vec!["01-01..12-31".parse().unwrap()],
+            ..WorkCalendar::default()
+        });
+        assert!(always_closed
+            .add_business_days(date(2023, 1, 6), 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_overlay_is_layered_on_data_and_rules() {
+        use crate::holiday::{HolidayKind, HolidayOrigin};
+        use assert_fs::prelude::*;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let file = dir.child("company.csv");
+        file.write_str(
+            "date,name,action
+2023-04-14,創立記念日,
+2023-01-09,,remove
+2030-04-15,創立記念日,
+",
+        )
+        .unwrap();
+        let overlay = Overlay::load_all(&[file.path().to_string_lossy().to_string()]).unwrap();
+
+        let service =
+            service_with_holidays(&[(date(2023, 1, 1), "元日"), (date(2023, 1, 9), "成人の日")])
+                .with_overlay(overlay);
+
+        assert!(!service.is_holiday(date(2023, 1, 9)).unwrap());
+        let lookup = service.lookup(date(2023, 4, 14)).unwrap();
+        assert_eq!(lookup.kind(), Some(HolidayKind::Custom));
+        assert!(matches!(
+            lookup.origin(),
+            Some(HolidayOrigin::Overlay { .. })
+        ));
+        assert!(!service.is_business_day(date(2023, 4, 14)).unwrap());
+
+        // Overlays also apply to years computed by the rule engine
+        assert!(service.is_holiday(date(2030, 4, 15)).unwrap());
+
+        let names: Vec<String> = service
+            .holidays_between(date(2023, 1, 1), date(2023, 4, 30))
+            .unwrap()
+            .into_iter()
+            .map(|h| h.name)
+            .collect();
+        assert_eq!(names, vec!["元日", "創立記念日"]);
+    }
+
+    #[test]
+    fn test_next_and_previous_holiday() {
+        let service = service_with_holidays(&[
+            (date(2023, 1, 1), "元日"),
+            (date(2023, 1, 2), "休日"),
+            (date(2023, 11, 23), "勤労感謝の日"),
+        ]);
+
+        let next = service.next_holiday(date(2023, 1, 1)).unwrap().unwrap();
+        assert_eq!(next.date, date(2023, 1, 2));
+        // Crosses from the data into a year computed by the rules
+        let next = service.next_holiday(date(2023, 11, 23)).unwrap().unwrap();
+        assert_eq!((next.date, next.name.as_str()), (date(2024, 1, 1), "元日"));
+
+        let previous = service
+            .previous_holiday(date(2023, 11, 23))
+            .unwrap()
+            .unwrap();
+        assert_eq!(previous.date, date(2023, 1, 2));
+        let previous = service.previous_holiday(date(2023, 1, 1)).unwrap().unwrap();
+        assert_eq!(previous.date, date(2022, 11, 23));
+        assert_eq!(service.previous_holiday(date(1948, 9, 1)).unwrap(), None);
+
+        let dates: Vec<NaiveDate> = service
+            .next_n_holidays(date(2023, 11, 1), 3)
+            .unwrap()
+            .into_iter()
+            .map(|h| h.date)
+            .collect();
+        assert_eq!(
+            dates,
+            vec![date(2023, 11, 23), date(2024, 1, 1), date(2024, 1, 8)]
+        );
+        assert!(service
+            .next_n_holidays(date(2023, 12, 1), 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            service
+                .next_n_holidays(date(2023, 12, 1), usize::MAX)
+                .unwrap()
+                .len(),
+            MAX_NEAREST_HOLIDAYS
+        );
+
+        let dates: Vec<NaiveDate> = service
+            .previous_n_holidays(date(2023, 1, 2), 2)
+            .unwrap()
+            .into_iter()
+            .map(|h| h.date)
+            .collect();
+        assert_eq!(dates, vec![date(2023, 1, 1), date(2022, 11, 23)]);
+    }
+
+    #[test]
+    fn test_queries_beyond_the_index() {
+        let service = service_with_holidays(&[(date(2023, 1, 1), "元日")]);
+        let index_end = service.loaded_index().unwrap().end();
+        let far = date(index_end.year() + 100, 1, 1);
+
+        // Ranges straddling the end of the index match year-by-year computation
+        let start = date(index_end.year() - 1, 1, 1);
+        let end = date(index_end.year() + 1, 12, 31);
+        let expected: Vec<Holiday> = (start.year()..=end.year())
+            .flat_map(rules::holidays_in_year)
+            .collect();
+        assert_eq!(service.holidays_between(start, end).unwrap(), expected);
+        assert_eq!(service.count_holidays(start, end).unwrap(), expected.len());
+
+        assert!(service.is_holiday(far).unwrap());
+        assert_eq!(
+            service.next_holiday(index_end).unwrap().unwrap().name,
+            "元日"
+        );
+        assert_eq!(
+            service.previous_holiday(far).unwrap(),
+            rules::holidays_in_year(far.year() - 1).pop()
+        );
+        assert_eq!(
+            service
+                .count_holidays(date(1900, 1, 1), date(1948, 12, 31))
+                .unwrap(),
+            3
+        );
+        assert!(service
+            .count_holidays(date(2023, 1, 2), date(2023, 1, 1))
+            .is_err());
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_initialize_offline_prefers_fresher_cache() {
+        use crate::cache::{CacheData, CacheMetadata};
+        use assert_fs::prelude::*;
+        use chrono::{TimeZone, Utc};
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let cache_file = dir.child("holidays.json");
+        let mut config = Config::default();
+        config.holiday_data.cache_file = cache_file.path().to_string_lossy().to_string();
+
+        // No cache file: the snapshot is used
+        let mut service = HolidayService::new(config.clone());
+        service.initialize_offline().unwrap();
+        assert_eq!(
+            service.data_source(),
+            Some(&DataSource::Embedded {
+                snapshot_date: embedded::snapshot_date()
+            })
+        );
+        assert!(service.is_holiday(date(2019, 10, 22)).unwrap());
+
+        let write_cache = |last_updated| {
+            let cache = CacheData {
+                metadata: CacheMetadata {
+                    last_updated,
+                    etag: None,
+                    last_etag_check: None,
+                    last_modified: None,
+                },
+                holidays: [(date(2099, 6, 1), "テストの日".to_string())].into(),
+            };
+            cache_file
+                .write_str(&serde_json::to_string(&cache).unwrap())
+                .unwrap();
+        };
+
+        write_cache(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap());
+        let mut service = HolidayService::new(config.clone());
+        service.initialize_offline().unwrap();
+        assert!(matches!(
+            service.data_source(),
+            Some(DataSource::Embedded { .. })
+        ));
+
+        write_cache(Utc::now() + Duration::days(1));
+        let mut service = HolidayService::new(config);
+        service.initialize_offline().unwrap();
+        assert_eq!(service.data_source(), Some(&DataSource::Cache));
+        assert!(service.is_holiday(date(2099, 6, 1)).unwrap());
+    }
+
+    #[test]
+    fn test_sync_initialization_from_files() {
+        use assert_fs::prelude::*;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.holiday_data.cache_file = dir
+            .child("missing.json")
+            .path()
+            .to_string_lossy()
+            .to_string();
+        assert!(HolidayService::new(config.clone())
+            .initialize_from_cache()
+            .is_err());
+
+        let csv_file = dir.child("syukujitsu.csv");
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(
+            "国民の祝日・休日月日,国民の祝日・休日名称\r\n2023/1/1,元日\r\n2023/1/2,休日\r\n",
+        );
+        csv_file.write_binary(&bytes).unwrap();
+        let path = csv_file.path().to_string_lossy().to_string();
+
+        let mut service = HolidayService::new(config);
+        service.initialize_from_csv_file(&path).unwrap();
+        assert_eq!(service.data_source(), Some(&DataSource::File { path }));
+        assert_eq!(
+            service.holiday_name(date(2023, 1, 2)).unwrap().as_deref(),
+            Some("休日")
+        );
+    }
+
+    #[test]
+    fn test_initialization_from_injected_source() {
+        use crate::source::{MemorySource, SourceMetadata};
+        use assert_fs::prelude::*;
+
+        let fixture = |name: &str, etag: &str| {
+            MemorySource::new([(date(2023, 11, 23), name.to_string())].into()).with_metadata(
+                SourceMetadata {
+                    etag: Some(etag.to_string()),
+                    last_modified: None,
+                },
+            )
+        };
+
+        let mut service = HolidayService::new(Config::default());
+        service
+            .initialize_from_source(&fixture("勤労感謝の日", "\"v1\""))
+            .unwrap();
+        assert_eq!(
+            service.data_source(),
+            Some(&DataSource::Source {
+                description: "in-memory holidays".to_string()
+            })
+        );
+        assert!(service.is_holiday(date(2023, 11, 23)).unwrap());
+
+        // The cache is written on first use and refreshed when the ETag changes
+        let dir = assert_fs::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.cache.strategy = crate::config::CacheStrategy::EtagBased;
+        config.cache.sanity = crate::sanity::SanityConfig::disabled();
+        config.holiday_data.cache_file = dir
+            .child("holidays.json")
+            .path()
+            .to_string_lossy()
+            .to_string();
+
+        let mut service =
+            HolidayService::new(config.clone()).with_source(fixture("勤労感謝の日", "\"v1\""));
+        service.initialize_blocking().unwrap();
+        assert_eq!(service.data_source(), Some(&DataSource::Cache));
+        assert!(dir.child("holidays.json").path().exists());
+
+        let mut service = HolidayService::new(config).with_source(fixture("改正", "\"v2\""));
+        service.initialize_blocking().unwrap();
+        assert_eq!(
+            service.holiday_name(date(2023, 11, 23)).unwrap().as_deref(),
+            Some("改正")
+        );
+    }
+
+    #[test]
+    fn test_lookup_reports_stale_cache() {
+        let mut service = service_with_holidays(&[(date(2023, 1, 1), "元日")]);
+        assert!(!service.lookup(date(2023, 1, 1)).unwrap().stale);
+
+        let holidays = service
+            .loaded_holidays()
+            .unwrap()
+            .clone()
+            .into_iter()
+            .collect();
+        service
+            .set_cached_holidays(CachedHolidays {
+                holidays,
+                stale: Some(Staleness {
+                    last_updated: chrono::Utc::now(),
+                    error: "timed out".to_string(),
+                }),
+                report: None,
+                diff: None,
+                discarded: None,
+            })
+            .unwrap();
+        assert!(service.lookup(date(2023, 1, 1)).unwrap().stale);
+        assert_eq!(service.staleness().unwrap().error, "timed out");
+    }
+
+    #[test]
+    fn test_banking_calendar_adds_year_end_closure() {
+        use crate::holiday::HolidayKind;
+
+        let service =
+            service_with_holidays(&[(date(2023, 1, 1), "元日"), (date(2023, 1, 2), "休日")])
+                .with_calendar(Calendar::Banking);
+
+        // 2023-01-02 stays a national substitute holiday
+        assert_eq!(
+            service.lookup(date(2023, 1, 2)).unwrap().kind(),
+            Some(HolidayKind::Substitute)
+        );
+        assert_eq!(
+            service.lookup(date(2023, 1, 3)).unwrap().kind(),
+            Some(HolidayKind::Closure)
+        );
+        assert!(service.is_holiday(date(2030, 12, 31)).unwrap());
+
+        let dates: Vec<NaiveDate> = service
+            .holidays_between(date(2022, 12, 30), date(2023, 1, 4))
+            .unwrap()
+            .into_iter()
+            .map(|h| h.date)
+            .collect();
+        assert_eq!(
+            dates,
+            vec![
+                date(2022, 12, 31),
+                date(2023, 1, 1),
+                date(2023, 1, 2),
+                date(2023, 1, 3)
+            ]
+        );
+        assert_eq!(
+            service.add_business_days(date(2022, 12, 30), 1).unwrap(),
+            date(2023, 1, 4)
+        );
+    }
+
+    #[test]
+    fn test_queries_require_initialization() {
+        let service = HolidayService::new(Config::default());
+        assert!(service.is_holiday(date(2023, 1, 1)).is_err());
+    }
+
     #[test]
     fn test_parse_date_flexible() {
         let config = Config::default();
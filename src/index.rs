@@ -0,0 +1,146 @@
+//! Sorted in-memory holiday index.
+//!
+//! Holidays are kept in a `Vec` sorted by date, so point lookups, range slices,
+//! counts and next/previous queries are binary searches.
+
+use crate::holiday::Holiday;
+use chrono::NaiveDate;
+
+/// All holidays between two dates, sorted by date, at most one per date
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HolidayIndex {
+    start: NaiveDate,
+    end: NaiveDate,
+    holidays: Vec<Holiday>,
+}
+
+impl HolidayIndex {
+    /// Index the holidays between `start` and `end`, inclusive; holidays outside
+    /// are dropped, and when several share a date the first one is kept
+    pub fn new(
+        start: NaiveDate,
+        end: NaiveDate,
+        holidays: impl IntoIterator<Item = Holiday>,
+    ) -> Self {
+        let mut holidays: Vec<Holiday> = holidays
+            .into_iter()
+            .filter(|holiday| holiday.date >= start && holiday.date <= end)
+            .collect();
+        holidays.sort_by_key(|holiday| holiday.date);
+        holidays.dedup_by_key(|holiday| holiday.date);
+        Self {
+            start,
+            end,
+            holidays,
+        }
+    }
+
+    /// First date the index is complete for
+    pub fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Last date the index is complete for
+    pub fn end(&self) -> NaiveDate {
+        self.end
+    }
+
+    /// True when `date` is within the indexed period, so a miss means it is not a holiday
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.holidays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holidays.is_empty()
+    }
+
+    pub fn holidays(&self) -> &[Holiday] {
+        &self.holidays
+    }
+
+    pub fn get(&self, date: NaiveDate) -> Option<&Holiday> {
+        self.holidays
+            .binary_search_by_key(&date, |holiday| holiday.date)
+            .ok()
+            .map(|i| &self.holidays[i])
+    }
+
+    /// Holidays between `start` and `end`, inclusive
+    pub fn range(&self, start: NaiveDate, end: NaiveDate) -> &[Holiday] {
+        let from = self.position(start);
+        let to = self.holidays.partition_point(|holiday| holiday.date <= end);
+        if from < to {
+            &self.holidays[from..to]
+        } else {
+            &[]
+        }
+    }
+
+    /// Number of holidays between `start` and `end`, inclusive
+    pub fn count(&self, start: NaiveDate, end: NaiveDate) -> usize {
+        self.range(start, end).len()
+    }
+
+    /// Holidays on or after `date`, in date order
+    pub fn since(&self, date: NaiveDate) -> &[Holiday] {
+        &self.holidays[self.position(date)..]
+    }
+
+    /// Holidays on or before `date`, in date order
+    pub fn until(&self, date: NaiveDate) -> &[Holiday] {
+        let to = self
+            .holidays
+            .partition_point(|holiday| holiday.date <= date);
+        &self.holidays[..to]
+    }
+
+    fn position(&self, date: NaiveDate) -> usize {
+        self.holidays.partition_point(|holiday| holiday.date < date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holiday::HolidayKind;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_index_queries() {
+        let index = HolidayIndex::new(
+            date(2023, 1, 1),
+            date(2023, 12, 31),
+            vec![
+                Holiday::new(date(2023, 1, 9), "成人の日", HolidayKind::National),
+                Holiday::new(date(2023, 1, 1), "元日", HolidayKind::National),
+                Holiday::new(date(2023, 1, 2), "休日", HolidayKind::Substitute),
+                Holiday::new(date(2023, 1, 1), "重複", HolidayKind::Custom),
+                Holiday::new(date(2024, 1, 1), "元日", HolidayKind::National),
+            ],
+        );
+
+        assert_eq!(index.len(), 3);
+        assert!(index.covers(date(2023, 12, 31)));
+        assert!(!index.covers(date(2024, 1, 1)));
+        assert_eq!(index.get(date(2023, 1, 1)).unwrap().name, "元日");
+        assert_eq!(index.get(date(2023, 1, 3)), None);
+
+        assert_eq!(index.count(date(2023, 1, 2), date(2023, 1, 9)), 2);
+        assert_eq!(index.count(date(2023, 1, 3), date(2023, 1, 8)), 0);
+        assert!(index.range(date(2023, 1, 9), date(2023, 1, 1)).is_empty());
+
+        assert_eq!(index.since(date(2023, 1, 3))[0].date, date(2023, 1, 9));
+        assert_eq!(
+            index.until(date(2023, 1, 8)).last().unwrap().date,
+            date(2023, 1, 2)
+        );
+        assert!(index.since(date(2023, 1, 10)).is_empty());
+    }
+}
@@ -10,9 +10,16 @@
 //!
 //! - Check if a specific date is a Japanese national holiday
 //! - List all holidays within a date range
+//! - Rule-based computation for years not yet published by the Cabinet Office
+//! - Cross-validation of the official CSV against the rule engine
+//! - Company holiday overlays loaded from local CSV/TOML files
+//! - Banking and Tokyo Stock Exchange calendars with the year-end closure (12/31–1/3)
+//! - Business-day arithmetic with a configurable work calendar (weekends, closed days, working days)
 //! - Support for multiple date formats (YYYYMMDD, YYYY-MM-DD, YYYY/MM/DD, YYYY年MM月DD日, etc.)
 //! - Automatic caching of holiday data with configurable update strategies
-//! - Async/await support using tokio
+//! - Optional embedded snapshot of the holiday data for offline use (`embedded` feature)
+//! - Async/await support using tokio (`fetch` feature, on by default)
+//! - A synchronous core without tokio or reqwest for local or embedded data
 //!
 //! ## Library Usage
 //!
@@ -24,6 +31,28 @@
 //! tokio = { version = "1", features = ["rt-multi-thread", "macros"] }
 //! ```
 //!
+//! Without the default `fetch` feature the crate does no networking and needs no
+//! async runtime; load the data from a local file (or the `embedded` snapshot):
+//!
+//! ```toml
+//! [dependencies]
+//! holidays_jp = { version = "0.3", default-features = false }
+//! ```
+//!
+//! ```rust,no_run
+//! use holidays_jp::{Config, HolidayService};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut service = HolidayService::new(Config::default());
+//!     // Or `initialize_from_cache()` to read `holiday_data.cache_file` as is
+//!     service.initialize_from_csv_file("syukujitsu.csv")?;
+//!
+//!     let lookup = service.get_holiday("2023-01-01")?;
+//!     println!("{:?}", lookup.name());
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ## Examples
 //!
 //! ### Check if a specific date is a holiday
@@ -31,21 +60,51 @@
 //! ```rust,no_run
 //! use holidays_jp::{HolidayService, Config};
 //!
+//! # #[cfg(feature = "fetch")]
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let config = Config::default();
 //!     let mut service = HolidayService::new(config);
 //!     service.initialize().await?;
 //!
-//!     let (is_holiday, holiday_name) = service.get_holiday("2023-01-01")?;
-//!     if is_holiday {
-//!         println!("2023-01-01 is a holiday: {}", holiday_name.unwrap());
+//!     let lookup = service.get_holiday("2023-01-01")?;
+//!     if let Some(holiday) = lookup.holiday {
+//!         println!("{} is a holiday: {} ({:?})", holiday.date, holiday.name, holiday.kind);
 //!     } else {
 //!         println!("2023-01-01 is not a holiday");
 //!     }
 //!
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "fetch"))]
+//! # fn main() {}
+//! ```
+//!
+//! ### Query with `chrono::NaiveDate` directly
+//!
+//! ```rust,no_run
+//! use chrono::NaiveDate;
+//! use holidays_jp::{HolidayService, Config};
+//!
+//! # #[cfg(feature = "fetch")]
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut service = HolidayService::new(Config::default());
+//!     service.initialize().await?;
+//!
+//!     let date = NaiveDate::from_ymd_opt(2023, 1, 9).unwrap();
+//!     if service.is_holiday(date)? {
+//!         println!("{}: {}", date, service.holiday_name(date)?.unwrap());
+//!     }
+//!
+//!     let start = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+//!     let end = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+//!     println!("{} holidays in 2023", service.holidays_between(start, end)?.len());
+//!
+//!     Ok(())
+//! }
+//! # #[cfg(not(feature = "fetch"))]
+//! # fn main() {}
 //! ```
 //!
 //! ### List holidays in a date range
@@ -53,6 +112,7 @@
 //! ```rust,no_run
 //! use holidays_jp::{HolidayService, Config};
 //!
+//! # #[cfg(feature = "fetch")]
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let config = Config::default();
@@ -60,19 +120,40 @@
 //!     service.initialize().await?;
 //!
 //!     let holidays = service.get_holidays_in_range("2023-01-01", "2023-12-31")?;
-//!     for (date, name) in holidays {
-//!         println!("{}: {}", date, name);
+//!     for holiday in holidays {
+//!         println!("{}: {}", holiday.date, holiday.name);
 //!     }
 //!
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "fetch"))]
+//! # fn main() {}
 //! ```
 
+pub mod business_day;
 pub mod cache;
+pub mod calendar;
 pub mod config;
 pub mod constants;
+pub mod diff;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+pub mod holiday;
 pub mod holiday_service;
+pub mod index;
+pub mod overlay;
+pub mod parse;
+pub mod rules;
+pub mod sanity;
+pub mod source;
+pub mod store;
+pub mod verify;
 
 // Re-export main types for easier use
-pub use config::Config;
-pub use holiday_service::HolidayService;
+pub use business_day::{Weekend, WorkCalendar};
+pub use calendar::Calendar;
+pub use config::{Config, ConfigBuilder};
+pub use holiday::{Holiday, HolidayKind, HolidayLookup, HolidayOrigin};
+pub use holiday_service::{DataSource, HolidayService};
+pub use source::HolidaySource;
+pub use store::CacheStore;
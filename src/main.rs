@@ -21,13 +21,44 @@
 //!   -V, --version                   Print version
 //! ```
 
-use holidays_jp::{config, holiday_service::HolidayService};
+use holidays_jp::{
+    config,
+    constants::CONFIG_PATH_ENV,
+    diff::{HolidayChange, HolidayDiff},
+    holiday_service::{HolidayService, MAX_NEAREST_HOLIDAYS},
+    parse::{self, ParseReport},
+    sanity::SanityError,
+    verify::{self, Discrepancy, VerifyReport},
+    Calendar, DataSource, Holiday, HolidayKind, HolidayLookup, HolidayOrigin,
+};
 
 use anyhow::{Context, Result};
-use std::{io::Write, process, str};
+use std::{io::Write, path::PathBuf, process, str};
 
-use clap::{arg, command, value_parser, ValueEnum};
-use serde_json;
+use chrono::NaiveDate;
+use clap::{
+    arg,
+    builder::{PossibleValuesParser, RangedU64ValueParser, TypedValueParser},
+    command, value_parser, ValueEnum,
+};
+
+/// `--calendar` of the commands looking up holidays
+fn calendar_arg() -> clap::Arg {
+    arg!(--calendar <CALENDAR>)
+        .help("Holiday calendar")
+        .long_help("national: national holidays only; banking: also the bank year-end closure (12/31-1/3); tse: Tokyo Stock Exchange closed days")
+        .value_parser(
+            PossibleValuesParser::new(Calendar::ALL.map(|calendar| calendar.as_str()))
+                .map(|s| s.parse::<Calendar>().unwrap()),
+        )
+        .default_value(Calendar::National.as_str())
+        .short('c')
+}
+
+/// `--count` of `next` and `prev`: from 1 to `MAX_NEAREST_HOLIDAYS`
+fn count_parser() -> RangedU64ValueParser<usize> {
+    RangedU64ValueParser::new().range(1..=MAX_NEAREST_HOLIDAYS as u64)
+}
 
 /// Print user-friendly error message with usage examples
 fn print_error_with_help(error: &anyhow::Error) {
@@ -46,6 +77,12 @@ fn print_error_with_help(error: &anyhow::Error) {
         eprintln!("\n💡 JSON output error:");
         eprintln!("   This might be due to invalid JSON serialization.");
         eprintln!("   Try using a different output format: -o human");
+    } else if let Some(sanity) = error.downcast_ref::<SanityError>() {
+        eprintln!("\n💡 The downloaded data failed the sanity checks; the cached data was kept:");
+        for violation in &sanity.violations {
+            eprintln!("   - {}", violation);
+        }
+        eprintln!("   If the change is expected, relax [cache.sanity] in config.toml.");
     } else if error.downcast_ref::<std::io::Error>().is_some() {
         eprintln!("\n💡 I/O error:");
         eprintln!("   Check if you have write permissions and sufficient disk space.");
@@ -66,9 +103,39 @@ enum OutputFormat {
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct HolidayResult {
-    date: String,
+    date: NaiveDate,
     is_holiday: bool,
     holiday_name: Option<String>,
+    kind: Option<HolidayKind>,
+    origin: Option<HolidayOrigin>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stale: bool,
+}
+
+impl From<&HolidayLookup> for HolidayResult {
+    fn from(lookup: &HolidayLookup) -> Self {
+        Self {
+            date: lookup.date,
+            is_holiday: lookup.is_holiday(),
+            holiday_name: lookup.name().map(|s| s.to_string()),
+            kind: lookup.kind(),
+            origin: lookup.origin().cloned(),
+            stale: lookup.stale,
+        }
+    }
+}
+
+impl From<&Holiday> for HolidayResult {
+    fn from(holiday: &Holiday) -> Self {
+        Self {
+            date: holiday.date,
+            is_holiday: true,
+            holiday_name: Some(holiday.name.clone()),
+            kind: Some(holiday.kind),
+            origin: Some(holiday.origin.clone()),
+            stale: false,
+        }
+    }
 }
 
 fn main() {
@@ -94,6 +161,12 @@ async fn run() -> Result<()> {
                 .global(true)
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            arg!(--config <PATH>)
+                .help("Configuration file to use")
+                .long_help("Load this configuration file instead of searching ./holidays_jp.toml, $XDG_CONFIG_HOME/holidays_jp/config.toml and $XDG_CONFIG_DIRS (also set by HOLIDAYS_JP_CONFIG). HOLIDAYS_JP_* environment variables override its values.")
+                .global(true),
+        )
         .subcommand(
             command!("check")
                 .about("Check if a specific date is a holiday (default)")
@@ -110,6 +183,7 @@ async fn run() -> Result<()> {
                         .short('d')
                         .conflicts_with("DATE"),
                 )
+                .arg(calendar_arg())
                 .arg(
                     arg!(--output <OUTPUT_FORMAT>)
                         .help("Output format")
@@ -122,7 +196,21 @@ async fn run() -> Result<()> {
         .subcommand(
             command!("update")
                 .about("Update holiday data from official source")
-                .long_about("Download the latest Japanese national holiday data from the official Cabinet Office CSV file and update the local database. This command requires an internet connection."),
+                .long_about("Download the latest Japanese national holiday data from the official Cabinet Office CSV file and update the local database, then show which holidays were added, removed or renamed. This command requires an internet connection.")
+                .arg(
+                    arg!(--"dry-run")
+                        .help("Show the changes without updating the cache")
+                        .long_help("Download and check the data and show what would change, leaving the cached data as it is")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--output <OUTPUT_FORMAT>)
+                        .help("Output format")
+                        .long_help("Choose how to display the changes: human (readable), json (structured data), or quiet (changed dates only)")
+                        .value_parser(value_parser!(OutputFormat))
+                        .default_value("human")
+                        .short('o'),
+                ),
         )
         .subcommand(
             command!("list")
@@ -140,6 +228,59 @@ async fn run() -> Result<()> {
                         .long_help("The end date of the range to search for holidays. Supports various formats: YYYYMMDD, YYYY-MM-DD, YYYY/MM/DD, YYYY年MM月DD日, etc.")
                         .short('e'),
                 )
+                .arg(calendar_arg())
+                .arg(
+                    arg!(--output <OUTPUT_FORMAT>)
+                        .help("Output format")
+                        .long_help("Choose how to display the results: human (readable list), json (structured data), or quiet (minimal format)")
+                        .value_parser(value_parser!(OutputFormat))
+                        .default_value("human")
+                        .short('o'),
+                ),
+        )
+        .subcommand(
+            command!("next")
+                .about("Show the next holidays after a date")
+                .long_about("Show the holidays following a date (default: today), nearest first. Years beyond the official data are computed from the National Holidays Act.")
+                .arg(
+                    arg!([DATE])
+                        .help("Date to search from (default: today)")
+                        .long_help("The date to search from, not counted itself. Supports various formats: YYYYMMDD, YYYY-MM-DD, YYYY/MM/DD, YYYY年MM月DD日, etc.")
+                )
+                .arg(
+                    arg!(--count <COUNT>)
+                        .help("Number of holidays to show")
+                        .value_parser(count_parser())
+                        .default_value("1")
+                        .short('n'),
+                )
+                .arg(calendar_arg())
+                .arg(
+                    arg!(--output <OUTPUT_FORMAT>)
+                        .help("Output format")
+                        .long_help("Choose how to display the results: human (readable list), json (structured data), or quiet (minimal format)")
+                        .value_parser(value_parser!(OutputFormat))
+                        .default_value("human")
+                        .short('o'),
+                ),
+        )
+        .subcommand(
+            command!("prev")
+                .about("Show the previous holidays before a date")
+                .long_about("Show the holidays preceding a date (default: today), nearest first.")
+                .arg(
+                    arg!([DATE])
+                        .help("Date to search from (default: today)")
+                        .long_help("The date to search from, not counted itself. Supports various formats: YYYYMMDD, YYYY-MM-DD, YYYY/MM/DD, YYYY年MM月DD日, etc.")
+                )
+                .arg(
+                    arg!(--count <COUNT>)
+                        .help("Number of holidays to show")
+                        .value_parser(count_parser())
+                        .default_value("1")
+                        .short('n'),
+                )
+                .arg(calendar_arg())
                 .arg(
                     arg!(--output <OUTPUT_FORMAT>)
                         .help("Output format")
@@ -154,26 +295,99 @@ async fn run() -> Result<()> {
                 .about("Display configuration information")
                 .long_about("Display the current configuration settings including data source URL, cache file location, and cache strategy."),
         )
+        .subcommand(
+            command!("init")
+                .about("Create a configuration file with the defaults")
+                .long_about("Write the default configuration to $XDG_CONFIG_HOME/holidays_jp/config.toml (~/.config/holidays_jp/config.toml by default). No configuration file is created otherwise.")
+                .arg(
+                    arg!(--force)
+                        .help("Overwrite an existing configuration file")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            command!("verify")
+                .about("Cross-check holiday data against the National Holidays Act rules")
+                .long_about("Compare the holiday data with holidays computed from the National Holidays Act, year by year, and report missing, extra and renamed entries. Exits with a non-zero status when any discrepancy is found.")
+                .arg(
+                    arg!(--file <CSV_FILE>)
+                        .help("CSV file to verify (default: the loaded holiday data)")
+                        .long_help("Path to a holiday CSV in the Cabinet Office format (Shift_JIS or UTF-8). When omitted, the currently loaded holiday data is verified.")
+                        .short('f'),
+                )
+                .arg(
+                    arg!(--output <OUTPUT_FORMAT>)
+                        .help("Output format")
+                        .long_help("Choose how to display the report: human (readable), json (structured data), or quiet (exit status only)")
+                        .value_parser(value_parser!(OutputFormat))
+                        .default_value("human")
+                        .short('o'),
+                ),
+        )
         .get_matches();
 
     // Check if verbose flag is set
     let verbose = matches.get_flag("verbose");
 
-    // Load configuration with verbosity setting
-    let config = config::Config::load_with_verbosity(verbose)?;
+    let config_path = matches.get_one::<String>("config").map(PathBuf::from);
+
+    if let Some(("init", sub_matches)) = matches.subcommand() {
+        let path = config_path
+            .or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from))
+            .or_else(config::Config::user_config_path)
+            .context("Cannot locate the configuration directory; set XDG_CONFIG_HOME or HOME")?;
+        config::Config::init(&path, sub_matches.get_flag("force"))?;
+        println!("✅ Created {}", path.display());
+        return Ok(());
+    }
+
+    // Load configuration with verbosity setting: --config > HOLIDAYS_JP_CONFIG > search paths,
+    // with HOLIDAYS_JP_* variables over the file
+    let mut builder = config::Config::builder().with_verbosity(verbose);
+    if let Some(path) = config_path {
+        builder = builder.with_file(path);
+    }
+    let config_file = builder.config_file();
+    let config = builder.build()?;
 
     // 祝日サービスを初期化
     let mut holiday_service = HolidayService::new(config.clone());
-    holiday_service.initialize().await
-        .context("Failed to initialize holiday service. Please check your internet connection and try again.")?;
+    // `update` downloads the data itself; loading it first could already refresh the
+    // cache and leave nothing to compare. `verify --file` only reads the file, so it
+    // works offline.
+    let needs_data = match matches.subcommand() {
+        Some(("update", _)) => false,
+        Some(("verify", sub_matches)) => !sub_matches.contains_id("file"),
+        _ => true,
+    };
+    if needs_data {
+        initialize_service(&mut holiday_service).await?;
+        if let Some(reason) = holiday_service.discarded_cache() {
+            eprintln!("⚠️  {}; downloaded the holiday data again", reason);
+        }
+        if let Some(stale) = holiday_service.staleness() {
+            eprintln!("⚠️  Could not refresh holiday data: {}", stale.error);
+            eprintln!(
+                "   Using the cached data from {}",
+                stale.last_updated.format("%Y-%m-%d %H:%M UTC")
+            );
+        }
+    }
 
     match matches.subcommand() {
         Some(("info", _)) => {
             // Display configuration with verbose output
             println!("📄 Configuration Information");
+            match &config_file {
+                Some(path) => println!("   Config file: {}", path.display()),
+                None => println!(
+                    "   Config file: none, using the defaults (create one with `holidays_jp init`)"
+                ),
+            }
             println!("   Source URL: {}", config.holiday_data.source_url);
             println!("   Cache file: {}", config.holiday_data.cache_file);
             println!("   Cache strategy: {:?}", config.cache.strategy);
+            println!("   Cache backend: {:?}", config.cache.backend);
             println!("   Max cache age: {} hours", config.cache.max_age_hours);
             println!(
                 "   ETag check interval: {} hours",
@@ -183,37 +397,119 @@ async fn run() -> Result<()> {
                 "   Force refresh on startup: {}",
                 config.cache.force_refresh_on_startup
             );
+            match holiday_service.data_source() {
+                Some(DataSource::Embedded { snapshot_date }) => {
+                    println!("   Data source: embedded snapshot of {}", snapshot_date)
+                }
+                _ => println!("   Data source: cache file"),
+            }
+            println!("   Overlays: {:?}", config.holiday_data.overlays);
+            println!("   Weekend: {:?}", config.work_calendar.weekend.days());
+            let closed_days: Vec<String> = config
+                .work_calendar
+                .closed_days
+                .iter()
+                .map(|period| period.to_string())
+                .collect();
+            println!("   Closed days: {:?}", closed_days);
+            println!(
+                "   Working days: {}",
+                config.work_calendar.working_days.len()
+            );
             return Ok(());
         }
+        Some(("verify", sub_matches)) => {
+            let output_format = sub_matches
+                .get_one::<OutputFormat>("output")
+                .unwrap()
+                .clone();
+
+            let (report, parse_report) = match sub_matches.get_one::<String>("file") {
+                Some(path) => {
+                    let bytes = std::fs::read(path)
+                        .with_context(|| format!("Failed to read CSV file: {}", path))?;
+                    // Malformed bytes are an error rather than replaced, like a download
+                    let parsed = parse::parse_bytes(&bytes).map_err(|error| {
+                        anyhow::anyhow!("Failed to parse CSV file {}: {:#}", path, error)
+                    })?;
+                    (verify::verify_names(&parsed.holidays), Some(parsed.report))
+                }
+                None => (holiday_service.verify()?, None),
+            };
+
+            write_verify_report(&report, parse_report.as_ref(), output_format)?;
+
+            if !report.is_clean() {
+                return Err(anyhow::anyhow!(
+                    "{} discrepancies found between the holiday data and the rules",
+                    report.discrepancies().count()
+                ));
+            }
+        }
         Some(("check", sub_matches)) => {
             // Check positional argument first, then fall back to --date option
             let date = sub_matches
                 .get_one::<String>("DATE")
                 .or_else(|| sub_matches.get_one::<String>("date"))
                 .map(|s| s.to_string())
-                .unwrap_or_else(|| HolidayService::get_today_date());
+                .unwrap_or_else(HolidayService::get_today_date);
             let output_format = sub_matches
                 .get_one::<OutputFormat>("output")
                 .unwrap()
                 .clone();
 
-            let (is_holiday, holiday_name) = holiday_service
+            let calendar = *sub_matches.get_one::<Calendar>("calendar").unwrap();
+            let holiday_service = holiday_service.with_calendar(calendar);
+
+            let lookup = holiday_service
                 .get_holiday(&date)
                 .context("Failed to check holiday status. Please verify your date format.")?;
 
-            write_holiday_result(&date, is_holiday, holiday_name.as_deref(), output_format)?;
+            write_holiday_result(&date, &lookup, output_format)?;
         }
-        Some(("update", _)) => {
-            println!("🔄 Updating holiday data from official source...");
-            // 強制更新のためにキャッシュを削除
-            let cache_path = &config.holiday_data.cache_file;
-            if std::path::Path::new(cache_path).exists() {
-                std::fs::remove_file(cache_path)?;
+        Some(("update", sub_matches)) => {
+            let dry_run = sub_matches.get_flag("dry-run");
+            let output_format = sub_matches
+                .get_one::<OutputFormat>("output")
+                .unwrap()
+                .clone();
+
+            if matches!(output_format, OutputFormat::Human) {
+                if dry_run {
+                    println!("🔍 Checking the official source for changes...");
+                } else {
+                    println!("🔄 Updating holiday data from official source...");
+                }
+            }
+            let context = if dry_run {
+                "Failed to check for changes. Please check your internet connection and try again."
+            } else {
+                "Failed to update holiday data. Please check your internet connection and try again."
+            };
+            if dry_run {
+                let preview = holiday_service.preview_update().await.context(context)?;
+                if let Some(reason) = &preview.discarded {
+                    eprintln!("⚠️  {}; comparing with no cached data", reason);
+                }
+                write_update_result(
+                    preview.report.as_ref(),
+                    preview.diff.as_ref(),
+                    dry_run,
+                    output_format,
+                )?;
+            } else {
+                // 強制的に再ダウンロード（失敗時は既存のキャッシュを保持）
+                holiday_service.update().await.context(context)?;
+                if let Some(reason) = holiday_service.discarded_cache() {
+                    eprintln!("⚠️  {}; replaced it", reason);
+                }
+                write_update_result(
+                    holiday_service.parse_report(),
+                    holiday_service.update_diff(),
+                    dry_run,
+                    output_format,
+                )?;
             }
-            // 再初期化してデータをダウンロード
-            holiday_service.initialize().await
-                .context("Failed to update holiday data. Please check your internet connection and try again.")?;
-            println!("✅ Holiday data updated successfully!");
         }
         Some(("list", sub_matches)) => {
             let start = sub_matches.get_one::<String>("start");
@@ -232,25 +528,46 @@ async fn run() -> Result<()> {
             let start_date = start.unwrap();
             let end_date = end.unwrap();
 
+            let calendar = *sub_matches.get_one::<Calendar>("calendar").unwrap();
+            let holiday_service = holiday_service.with_calendar(calendar);
+
             let holidays = holiday_service
                 .get_holidays_in_range(start_date, end_date)
                 .context("Failed to get holidays in range. Please check your date formats.")?;
 
             write_holidays_list(start_date, end_date, &holidays, output_format)?;
         }
+        Some((direction @ ("next" | "prev"), sub_matches)) => {
+            let date = sub_matches
+                .get_one::<String>("DATE")
+                .map(|s| s.to_string())
+                .unwrap_or_else(HolidayService::get_today_date);
+            let count = *sub_matches.get_one::<usize>("count").unwrap();
+            let output_format = sub_matches
+                .get_one::<OutputFormat>("output")
+                .unwrap()
+                .clone();
+            let calendar = *sub_matches.get_one::<Calendar>("calendar").unwrap();
+            let holiday_service = holiday_service.with_calendar(calendar);
+
+            let from = HolidayService::parse_date(&date)
+                .context("Failed to search holidays. Please verify your date format.")?;
+            let holidays = if direction == "next" {
+                holiday_service.next_n_holidays(from, count)?
+            } else {
+                holiday_service.previous_n_holidays(from, count)?
+            };
+
+            write_nearest_holidays(from, direction == "next", &holidays, output_format)?;
+        }
         None => {
             // Default behavior: check today's date
             let today = HolidayService::get_today_date();
-            let (is_holiday, holiday_name) = holiday_service
+            let lookup = holiday_service
                 .get_holiday(&today)
                 .context("Failed to check holiday status. Please verify your date format.")?;
 
-            write_holiday_result(
-                &today,
-                is_holiday,
-                holiday_name.as_deref(),
-                OutputFormat::Human,
-            )?;
+            write_holiday_result(&today, &lookup, OutputFormat::Human)?;
         }
         _ => unreachable!(),
     }
@@ -258,36 +575,50 @@ async fn run() -> Result<()> {
     Ok(())
 }
 
+/// Load the holiday data, falling back to the embedded snapshot when the
+/// cache cannot be loaded or downloaded
+#[cfg(feature = "embedded")]
+async fn initialize_service(holiday_service: &mut HolidayService) -> Result<()> {
+    if let Err(error) = holiday_service.initialize().await {
+        eprintln!("⚠️  Failed to load holiday data: {}", error);
+        eprintln!("   Using the embedded holiday data instead");
+        holiday_service.initialize_offline()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "embedded"))]
+async fn initialize_service(holiday_service: &mut HolidayService) -> Result<()> {
+    holiday_service.initialize().await
+        .context("Failed to initialize holiday service. Please check your internet connection and try again.")
+}
+
 fn write_holiday_result(
     date: &str,
-    is_holiday: bool,
-    holiday_name: Option<&str>,
+    lookup: &HolidayLookup,
     output_format: OutputFormat,
 ) -> Result<()> {
     match output_format {
         OutputFormat::Human => {
-            if is_holiday {
+            if let Some(holiday) = &lookup.holiday {
                 writeln!(
                     std::io::stdout(),
-                    "{} is holiday({})",
+                    "{} is holiday({}){}",
                     date,
-                    holiday_name.unwrap_or("")
+                    holiday.name,
+                    origin_suffix(&holiday.origin)
                 )?;
             } else {
                 writeln!(std::io::stdout(), "{} is not a holiday", date)?;
             }
         }
         OutputFormat::Json => {
-            let result = HolidayResult {
-                date: date.to_string(),
-                is_holiday,
-                holiday_name: holiday_name.map(|s| s.to_string()),
-            };
+            let result = HolidayResult::from(lookup);
             writeln!(std::io::stdout(), "{}", serde_json::to_string(&result)?)?;
         }
         OutputFormat::Quiet => {
-            if is_holiday {
-                writeln!(std::io::stdout(), "{}", holiday_name.unwrap_or(""))?;
+            if let Some(holiday_name) = lookup.name() {
+                writeln!(std::io::stdout(), "{}", holiday_name)?;
             }
             // For quiet mode, don't output anything for non-holidays
         }
@@ -295,10 +626,18 @@ fn write_holiday_result(
     Ok(())
 }
 
+/// Human-readable note on where a holiday came from; empty for the official data
+fn origin_suffix(origin: &HolidayOrigin) -> String {
+    match origin {
+        HolidayOrigin::Official => String::new(),
+        origin => format!(" [{}]", origin),
+    }
+}
+
 fn write_holidays_list(
     start_date: &str,
     end_date: &str,
-    holidays: &[(String, String)],
+    holidays: &[Holiday],
     output_format: OutputFormat,
 ) -> Result<()> {
     if holidays.is_empty() {
@@ -325,19 +664,18 @@ fn write_holidays_list(
         match output_format {
             OutputFormat::Human => {
                 println!("Holidays in range ({} to {}):", start_date, end_date);
-                for (date, name) in holidays {
-                    println!("  {} - {}", date, name);
+                for holiday in holidays {
+                    println!(
+                        "  {} - {}{}",
+                        holiday.date,
+                        holiday.name,
+                        origin_suffix(&holiday.origin)
+                    );
                 }
             }
             OutputFormat::Json => {
-                let holiday_list: Vec<HolidayResult> = holidays
-                    .iter()
-                    .map(|(date, name)| HolidayResult {
-                        date: date.clone(),
-                        is_holiday: true,
-                        holiday_name: Some(name.clone()),
-                    })
-                    .collect();
+                let holiday_list: Vec<HolidayResult> =
+                    holidays.iter().map(HolidayResult::from).collect();
                 let result = serde_json::json!({
                     "start_date": start_date,
                     "end_date": end_date,
@@ -346,10 +684,200 @@ fn write_holidays_list(
                 println!("{}", serde_json::to_string_pretty(&result)?);
             }
             OutputFormat::Quiet => {
-                for (date, name) in holidays {
-                    println!("{} - {}", date, name);
+                for holiday in holidays {
+                    println!("{} - {}", holiday.date, holiday.name);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_nearest_holidays(
+    from: NaiveDate,
+    forward: bool,
+    holidays: &[Holiday],
+    output_format: OutputFormat,
+) -> Result<()> {
+    let direction = if forward { "after" } else { "before" };
+    match output_format {
+        OutputFormat::Human => {
+            if holidays.is_empty() {
+                println!("No holidays found {} {}", direction, from);
+            } else {
+                println!("Holidays {} {}:", direction, from);
+                for holiday in holidays {
+                    println!(
+                        "  {} - {}{}",
+                        holiday.date,
+                        holiday.name,
+                        origin_suffix(&holiday.origin)
+                    );
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let holiday_list: Vec<HolidayResult> =
+                holidays.iter().map(HolidayResult::from).collect();
+            let result = serde_json::json!({
+                "from": from,
+                "direction": if forward { "next" } else { "prev" },
+                "holidays": holiday_list
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Quiet => {
+            for holiday in holidays {
+                println!("{} - {}", holiday.date, holiday.name);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_update_result(
+    report: Option<&ParseReport>,
+    diff: Option<&HolidayDiff>,
+    dry_run: bool,
+    output_format: OutputFormat,
+) -> Result<()> {
+    let changes = diff.map_or(&[][..], |diff| &diff.changes[..]);
+    match output_format {
+        OutputFormat::Human => {
+            if dry_run {
+                println!("🔍 Dry run: the cached holiday data was not changed");
+            } else {
+                println!("✅ Holiday data updated successfully!");
+            }
+            if let Some(report) = report {
+                print_parse_report(report);
+            }
+            if let Some(diff) = diff {
+                if diff.is_empty() {
+                    println!("   No changes to the holiday data");
+                } else {
+                    println!(
+                        "   {} {}: {} added, {} removed, {} renamed",
+                        changes.len(),
+                        if changes.len() == 1 {
+                            "change"
+                        } else {
+                            "changes"
+                        },
+                        diff.added(),
+                        diff.removed(),
+                        diff.renamed()
+                    );
                 }
             }
+            for change in changes {
+                println!("   {}", format_change(change));
+            }
+        }
+        OutputFormat::Json => {
+            let result = serde_json::json!({
+                "dry_run": dry_run,
+                "report": report,
+                "changes": changes,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Quiet => {
+            for change in changes {
+                println!("{}", format_change(change));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_parse_report(report: &ParseReport) {
+    println!(
+        "   Parsed {} rows ({}): {} holidays, {} skipped",
+        report.rows_read,
+        report.encoding,
+        report.holidays,
+        report.skipped.len()
+    );
+    for row in &report.skipped {
+        println!("   ⚠️  Line {}: {}", row.line, row.reason);
+    }
+}
+
+fn format_change(change: &HolidayChange) -> String {
+    match change {
+        HolidayChange::Added { date, name } => format!("+ {} {}", date, name),
+        HolidayChange::Removed { date, name } => format!("- {} {}", date, name),
+        HolidayChange::Renamed {
+            date,
+            old_name,
+            new_name,
+        } => format!("~ {} {} → {}", date, old_name, new_name),
+    }
+}
+
+/// `parse_report` is given when a CSV file was verified
+fn write_verify_report(
+    report: &VerifyReport,
+    parse_report: Option<&ParseReport>,
+    output_format: OutputFormat,
+) -> Result<()> {
+    match output_format {
+        OutputFormat::Human => {
+            if let Some(parse_report) = parse_report {
+                print_parse_report(parse_report);
+            }
+            let (first, last) = match (report.years.first(), report.years.last()) {
+                (Some(first), Some(last)) => (first.year, last.year),
+                _ => {
+                    println!("No holiday data to verify");
+                    return Ok(());
+                }
+            };
+            for year in report.years.iter().filter(|y| !y.discrepancies.is_empty()) {
+                println!(
+                    "{}: {} in data, {} computed",
+                    year.year, year.data_count, year.computed_count
+                );
+                for discrepancy in &year.discrepancies {
+                    match discrepancy {
+                        Discrepancy::Missing { date, expected } => {
+                            println!("  - {} missing: {}", date, expected)
+                        }
+                        Discrepancy::Extra { date, name } => {
+                            println!("  + {} extra: {}", date, name)
+                        }
+                        Discrepancy::Renamed {
+                            date,
+                            name,
+                            expected,
+                        } => println!("  ~ {} renamed: {} (expected {})", date, name, expected),
+                    }
+                }
+            }
+            if report.is_clean() {
+                println!(
+                    "✅ Holiday data for {} to {} matches the rules",
+                    first, last
+                );
+            } else {
+                println!(
+                    "⚠️  {} discrepancies found in {} to {}",
+                    report.discrepancies().count(),
+                    first,
+                    last
+                );
+            }
+        }
+        OutputFormat::Json => {
+            let mut result = serde_json::to_value(report)?;
+            if let Some(parse_report) = parse_report {
+                result["report"] = serde_json::to_value(parse_report)?;
+            }
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Quiet => {
+            // Quiet mode only reports through the exit status
         }
     }
     Ok(())
@@ -359,12 +887,26 @@ fn write_holidays_list(
 mod tests {
     use super::*;
 
+    fn new_year_lookup() -> HolidayLookup {
+        let date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        HolidayLookup {
+            date,
+            holiday: Some(Holiday::new(date, "元日", HolidayKind::National)),
+            stale: false,
+        }
+    }
+
     #[test]
     fn test_write_holiday_result_human() -> Result<()> {
         // テストは実際の出力を確認するため、stdoutをキャプチャする必要がある
         // ここでは基本的な動作確認のみ
-        write_holiday_result("20230101", true, Some("元日"), OutputFormat::Human)?;
-        write_holiday_result("20230102", false, None, OutputFormat::Human)?;
+        write_holiday_result("20230101", &new_year_lookup(), OutputFormat::Human)?;
+        let not_holiday = HolidayLookup {
+            date: NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+            holiday: None,
+            stale: false,
+        };
+        write_holiday_result("20230103", &not_holiday, OutputFormat::Human)?;
         Ok(())
     }
 
@@ -372,7 +914,17 @@ mod tests {
     fn test_write_holiday_result_json() -> Result<()> {
         // テストは実際の出力を確認するため、stdoutをキャプチャする必要がある
         // ここでは基本的な動作確認のみ
-        write_holiday_result("20230101", true, Some("元日"), OutputFormat::Json)?;
+        write_holiday_result("20230101", &new_year_lookup(), OutputFormat::Json)?;
         Ok(())
     }
+
+    #[test]
+    fn test_holiday_result_from_lookup() {
+        let result = HolidayResult::from(&new_year_lookup());
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            json,
+            r#"{"date":"2023-01-01","is_holiday":true,"holiday_name":"元日","kind":"national","origin":{"type":"official"}}"#
+        );
+    }
 }
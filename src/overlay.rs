@@ -0,0 +1,280 @@
+//! Custom holiday overlays loaded from local files.
+//!
+//! Overlays add holidays (e.g. 創立記念日, summer closure) or remove national
+//! holidays on top of the official data. They are listed in
+//! `holiday_data.overlays` and may be TOML or CSV files:
+//!
+//! ```toml
+//! [[holidays]]
+//! date = "2024-04-15"
+//! name = "創立記念日"
+//!
+//! [[holidays]]
+//! date = "2024-08-13"
+//! end = "2024-08-16"
+//! name = "夏季休業"
+//!
+//! [[remove]]
+//! date = "2024-07-15"
+//! ```
+//!
+//! ```csv
+//! date,name,action
+//! 2024/04/15,創立記念日,
+//! 2024/07/15,,remove
+//! ```
+
+use crate::holiday::{Holiday, HolidayKind, HolidayOrigin};
+use crate::holiday_service::HolidayService;
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct TomlOverlay {
+    #[serde(default)]
+    holidays: Vec<TomlHoliday>,
+    #[serde(default)]
+    remove: Vec<TomlRemoval>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TomlHoliday {
+    date: NaiveDate,
+    end: Option<NaiveDate>,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TomlRemoval {
+    date: NaiveDate,
+    end: Option<NaiveDate>,
+}
+
+/// Additions and removals from one or more overlay files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overlay {
+    additions: HashMap<NaiveDate, Holiday>,
+    /// Removed dates and the overlay file that removed them
+    removals: HashMap<NaiveDate, String>,
+}
+
+impl Overlay {
+    /// Load overlay files in order; a later file overrides earlier ones for the same date
+    pub fn load_all(paths: &[String]) -> Result<Self> {
+        let mut overlay = Overlay::default();
+        for path in paths {
+            overlay.load_file(path)?;
+        }
+        Ok(overlay)
+    }
+
+    pub fn load_file(&mut self, path: &str) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read overlay file: {}", path))?;
+
+        let is_toml = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        if is_toml {
+            self.load_toml(path, &content)
+        } else {
+            self.load_csv(path, &content)
+        }
+        .with_context(|| format!("Failed to parse overlay file: {}", path))
+    }
+
+    fn load_toml(&mut self, path: &str, content: &str) -> Result<()> {
+        let parsed: TomlOverlay = toml::from_str(content)?;
+        for holiday in parsed.holidays {
+            for date in days(holiday.date, holiday.end)? {
+                self.add(path, date, &holiday.name);
+            }
+        }
+        for removal in parsed.remove {
+            for date in days(removal.date, removal.end)? {
+                self.remove(path, date);
+            }
+        }
+        Ok(())
+    }
+
+    fn load_csv(&mut self, path: &str, content: &str) -> Result<()> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(content.as_bytes());
+
+        for result in rdr.records() {
+            let record = result?;
+            let date = HolidayService::parse_date(record.get(0).unwrap_or("").trim())?;
+            let name = record.get(1).unwrap_or("").trim();
+            match record.get(2).unwrap_or("").trim() {
+                "" | "add" => {
+                    if name.is_empty() {
+                        return Err(anyhow::anyhow!("Missing holiday name for {}", date));
+                    }
+                    self.add(path, date, name);
+                }
+                "remove" => self.remove(path, date),
+                action => {
+                    return Err(anyhow::anyhow!(
+                        "Unknown action '{}' for {}: expected 'add' or 'remove'",
+                        action,
+                        date
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, path: &str, date: NaiveDate, name: &str) {
+        self.removals.remove(&date);
+        let holiday =
+            Holiday::new(date, name, HolidayKind::Custom).with_origin(HolidayOrigin::Overlay {
+                path: path.to_string(),
+            });
+        self.additions.insert(date, holiday);
+    }
+
+    fn remove(&mut self, path: &str, date: NaiveDate) {
+        self.additions.remove(&date);
+        self.removals.insert(date, path.to_string());
+    }
+
+    /// The overlay file that removed the holiday on `date`, if any
+    pub fn removed_by(&self, date: NaiveDate) -> Option<&str> {
+        self.removals.get(&date).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.additions.is_empty() && self.removals.is_empty()
+    }
+
+    /// Apply the overlay to the base holiday of a single date
+    pub fn apply(&self, date: NaiveDate, base: Option<Holiday>) -> Option<Holiday> {
+        if let Some(addition) = self.additions.get(&date) {
+            return Some(addition.clone());
+        }
+        if self.removals.contains_key(&date) {
+            return None;
+        }
+        base
+    }
+
+    /// Apply the overlay to the base holidays between `start` and `end`, inclusive
+    pub fn apply_range(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        base: Vec<Holiday>,
+    ) -> Vec<Holiday> {
+        if self.is_empty() {
+            return base;
+        }
+
+        let mut holidays: Vec<Holiday> = base
+            .into_iter()
+            .filter(|holiday| {
+                !self.removals.contains_key(&holiday.date)
+                    && !self.additions.contains_key(&holiday.date)
+            })
+            .chain(
+                self.additions
+                    .values()
+                    .filter(|holiday| holiday.date >= start && holiday.date <= end)
+                    .cloned(),
+            )
+            .collect();
+        holidays.sort_by_key(|holiday| holiday.date);
+        holidays
+    }
+}
+
+fn days(start: NaiveDate, end: Option<NaiveDate>) -> Result<Vec<NaiveDate>> {
+    let end = end.unwrap_or(start);
+    if end < start {
+        return Err(anyhow::anyhow!(
+            "Overlay range end {} is before its start {}",
+            end,
+            start
+        ));
+    }
+    let mut dates = Vec::new();
+    let mut current = start;
+    while current <= end {
+        dates.push(current);
+        current += Duration::days(1);
+    }
+    Ok(dates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_load_toml_and_csv_overlays() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let toml_file = dir.child("company.toml");
+        toml_file
+            .write_str(
+                r#"
+                [[holidays]]
+                date = "2024-08-13"
+                end = "2024-08-14"
+                name = "夏季休業"
+
+                [[remove]]
+                date = "2024-07-15"
+                "#,
+            )
+            .unwrap();
+        let csv_file = dir.child("shift.csv");
+        csv_file
+            .write_str("date,name,action\n2024/04/15,創立記念日,\n2024/08/14,,remove\n")
+            .unwrap();
+
+        let toml_path = toml_file.path().to_string_lossy().to_string();
+        let csv_path = csv_file.path().to_string_lossy().to_string();
+        let overlay = Overlay::load_all(&[toml_path.clone(), csv_path.clone()]).unwrap();
+
+        let summer = overlay.apply(date(2024, 8, 13), None).unwrap();
+        assert_eq!(summer.name, "夏季休業");
+        assert_eq!(summer.kind, HolidayKind::Custom);
+        assert_eq!(summer.origin, HolidayOrigin::Overlay { path: toml_path });
+
+        // The later CSV file removes a day added by the TOML file
+        assert_eq!(overlay.apply(date(2024, 8, 14), None), None);
+
+        assert!(overlay.removed_by(date(2024, 7, 15)).is_some());
+        let umi_no_hi = Holiday::new(date(2024, 7, 15), "海の日", HolidayKind::National);
+        assert_eq!(overlay.apply(date(2024, 7, 15), Some(umi_no_hi)), None);
+
+        let holidays = overlay.apply_range(date(2024, 4, 1), date(2024, 4, 30), Vec::new());
+        assert_eq!(holidays.len(), 1);
+        assert_eq!(
+            holidays[0].origin,
+            HolidayOrigin::Overlay { path: csv_path }
+        );
+    }
+
+    #[test]
+    fn test_invalid_overlay() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let file = dir.child("bad.csv");
+        file.write_str("date,name,action\n2024/04/15,創立記念日,move\n")
+            .unwrap();
+
+        let path = file.path().to_string_lossy().to_string();
+        assert!(Overlay::load_all(&[path]).is_err());
+        assert!(Overlay::load_all(&["missing.toml".to_string()]).is_err());
+    }
+}
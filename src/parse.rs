@@ -0,0 +1,221 @@
+//! Decoding and validating the Cabinet Office CSV.
+//!
+//! The CSV has been published as Shift_JIS with a `国民の祝日・休日月日,国民の祝日・休日名称`
+//! header and `YYYY/M/D` dates. Format changes upstream should fail loudly rather than
+//! quietly produce a partial holiday map, so the encoding is detected explicitly, the
+//! header is checked, and every skipped row is reported with the reason.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Date formats accepted in the first column; `%m` and `%d` also accept zero padding
+const DATE_FORMATS: &[&str] = &["%Y/%m/%d", "%Y-%m-%d"];
+
+/// A data row that was not used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedRow {
+    /// 1-based line number in the CSV
+    pub line: u64,
+    pub reason: String,
+}
+
+/// What parsing a CSV found
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ParseReport {
+    /// Detected encoding, e.g. `Shift_JIS` or `UTF-8`
+    pub encoding: String,
+    /// Data rows read, not counting the header
+    pub rows_read: usize,
+    /// Holidays parsed
+    pub holidays: usize,
+    /// Columns after the date and name, which are ignored
+    pub extra_columns: usize,
+    /// Rows skipped because their date was already listed
+    pub duplicates: usize,
+    pub skipped: Vec<SkippedRow>,
+}
+
+/// A parsed CSV and its report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCsv {
+    pub holidays: HashMap<NaiveDate, String>,
+    pub report: ParseReport,
+}
+
+/// The encoding of `bytes`: from the byte order mark if there is one, UTF-8 if the
+/// bytes are valid UTF-8, Shift_JIS otherwise
+pub fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        SHIFT_JIS
+    }
+}
+
+/// Decode `bytes` with the detected encoding; malformed input is an error, not replaced
+pub fn decode(bytes: &[u8]) -> Result<(String, &'static Encoding)> {
+    let encoding = detect_encoding(bytes);
+    let text = match Encoding::for_bom(bytes) {
+        Some((_, bom_length)) => &bytes[bom_length..],
+        None => bytes,
+    };
+    let text = encoding
+        .decode_without_bom_handling_and_without_replacement(text)
+        .with_context(|| format!("CSV is not valid {}", encoding.name()))?;
+    Ok((text.into_owned(), encoding))
+}
+
+/// Decode and parse a holiday CSV
+pub fn parse_bytes(bytes: &[u8]) -> Result<ParsedCsv> {
+    let (text, encoding) = decode(bytes)?;
+    parse_text_as(&text, encoding.name())
+}
+
+/// Parse an already decoded holiday CSV
+pub fn parse_text(text: &str) -> Result<ParsedCsv> {
+    parse_text_as(text.trim_start_matches('\u{feff}'), UTF_8.name())
+}
+
+fn parse_text_as(text: &str, encoding: &str) -> Result<ParsedCsv> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let header = reader
+        .headers()
+        .context("Failed to read CSV header")?
+        .clone();
+    check_header(&header)?;
+
+    let mut holidays = HashMap::new();
+    let mut report = ParseReport {
+        encoding: encoding.to_string(),
+        ..ParseReport::default()
+    };
+
+    for result in reader.records() {
+        let record = result.context("Failed to read CSV")?;
+        let line = record.position().map_or(0, |position| position.line());
+        report.rows_read += 1;
+        report.extra_columns = report.extra_columns.max(record.len().saturating_sub(2));
+
+        let mut skip = |reason: String| report.skipped.push(SkippedRow { line, reason });
+        let date_field = record.get(0).unwrap_or("").trim();
+        let name = record.get(1).unwrap_or("").trim();
+
+        if record.iter().all(|field| field.trim().is_empty()) {
+            skip("blank row".to_string());
+            continue;
+        }
+        let Some(date) = parse_date(date_field) else {
+            skip(format!("invalid date '{}'", date_field));
+            continue;
+        };
+        if name.is_empty() {
+            skip(format!("no name for {}", date));
+            continue;
+        }
+        if holidays.contains_key(&date) {
+            skip(format!("duplicate date {}", date));
+            report.duplicates += 1;
+            continue;
+        }
+        holidays.insert(date, name.to_string());
+    }
+
+    report.holidays = holidays.len();
+    Ok(ParsedCsv { holidays, report })
+}
+
+/// The first two columns must be the date and the name, e.g. `国民の祝日・休日月日` and
+/// `国民の祝日・休日名称` (older files say `国民の祝日月日` and `国民の祝日名称`)
+fn check_header(header: &csv::StringRecord) -> Result<()> {
+    let date = header.get(0).unwrap_or("").trim();
+    let name = header.get(1).unwrap_or("").trim();
+    if date.ends_with("月日") && name.ends_with("名称") {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "Unexpected CSV header '{}': expected a date (…月日) and a name (…名称) column",
+        header.iter().collect::<Vec<_>>().join(",")
+    ))
+}
+
+fn parse_date(field: &str) -> Option<NaiveDate> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(field, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "国民の祝日・休日月日,国民の祝日・休日名称";
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_detects_encodings() {
+        let text = format!("{}\r\n2023/1/1,元日\r\n", HEADER);
+        let (shift_jis, _, _) = SHIFT_JIS.encode(&text);
+        let with_bom = [b"\xef\xbb\xbf".as_slice(), text.as_bytes()].concat();
+
+        for (bytes, encoding) in [
+            (shift_jis.to_vec(), "Shift_JIS"),
+            (text.as_bytes().to_vec(), "UTF-8"),
+            (with_bom, "UTF-8"),
+        ] {
+            let parsed = parse_bytes(&bytes).unwrap();
+            assert_eq!(parsed.report.encoding, encoding);
+            assert_eq!(parsed.holidays[&date(2023, 1, 1)], "元日");
+        }
+
+        // Neither UTF-8 nor Shift_JIS
+        assert!(parse_bytes(b"\x80\x80\x80").is_err());
+    }
+
+    #[test]
+    fn test_reports_skipped_rows() {
+        let csv = format!(
+            "{},備考\n2023/01/01,元日,\n2023-01-09,成人の日\nnot a date,x\n2023/2/11,\n2023/1/1,元日\n,\n",
+            HEADER
+        );
+        let parsed = parse_text(&csv).unwrap();
+
+        assert_eq!(parsed.holidays.len(), 2);
+        assert_eq!(parsed.report.rows_read, 6);
+        assert_eq!(parsed.report.extra_columns, 1);
+        assert_eq!(parsed.report.duplicates, 1);
+        let reasons: Vec<(u64, &str)> = parsed
+            .report
+            .skipped
+            .iter()
+            .map(|row| (row.line, row.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (4, "invalid date 'not a date'"),
+                (5, "no name for 2023-02-11"),
+                (6, "duplicate date 2023-01-01"),
+                (7, "blank row"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_unexpected_header() {
+        assert!(parse_text("国民の祝日月日,国民の祝日名称\n2019/1/1,元日\n").is_ok());
+        assert!(parse_text("date,name\n2023/1/1,元日\n").is_err());
+        assert!(parse_text("<!DOCTYPE html>\n<html></html>\n").is_err());
+    }
+}
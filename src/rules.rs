@@ -0,0 +1,556 @@
+//! Rule-based holiday computation following the National Holidays Act
+//! (国民の祝日に関する法律).
+//!
+//! Every rule carries the period during which it was in force, so that a date is
+//! judged by the law as it stood on that day. The Act took effect on 1948-07-20;
+//! no holidays are computed before that. Used to answer for years the Cabinet
+//! Office CSV does not cover (before 1955 and after its last year).
+
+use crate::holiday::{Holiday, HolidayKind, HolidayOrigin, GENERIC_HOLIDAY_NAME};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::BTreeMap;
+
+/// A calendar date as (year, month, day), usable in const tables
+pub type Ymd = (i32, u32, u32);
+
+/// Enforcement of the National Holidays Act
+pub const ACT_EFFECTIVE: Ymd = (1948, 7, 20);
+
+/// How the date of a holiday is determined within a year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateRule {
+    /// A fixed month and day
+    Fixed { month: u32, day: u32 },
+    /// The n-th Monday of a month (Happy Monday system)
+    NthMonday { month: u32, n: u8 },
+    /// 春分日
+    VernalEquinox,
+    /// 秋分日
+    AutumnalEquinox,
+}
+
+impl DateRule {
+    pub fn date_in(&self, year: i32) -> Option<NaiveDate> {
+        match *self {
+            DateRule::Fixed { month, day } => NaiveDate::from_ymd_opt(year, month, day),
+            DateRule::NthMonday { month, n } => {
+                NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n)
+            }
+            DateRule::VernalEquinox => {
+                vernal_equinox_day(year).and_then(|day| NaiveDate::from_ymd_opt(year, 3, day))
+            }
+            DateRule::AutumnalEquinox => {
+                autumnal_equinox_day(year).and_then(|day| NaiveDate::from_ymd_opt(year, 9, day))
+            }
+        }
+    }
+}
+
+/// Period during which a rule is in force; `until` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Effective {
+    pub from: Ymd,
+    pub until: Option<Ymd>,
+}
+
+impl Effective {
+    pub const fn from(from: Ymd) -> Self {
+        Self { from, until: None }
+    }
+
+    pub const fn between(from: Ymd, until: Ymd) -> Self {
+        Self {
+            from,
+            until: Some(until),
+        }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        let ymd = (date.year(), date.month(), date.day());
+        let before_until = match self.until {
+            Some(until) => ymd < until,
+            None => true,
+        };
+        ymd >= self.from && before_until
+    }
+}
+
+/// A named national holiday, the rule that places it and when that rule applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HolidayRule {
+    pub name: &'static str,
+    pub date: DateRule,
+    pub effective: Effective,
+}
+
+impl HolidayRule {
+    /// Date of this holiday in `year`, if the rule was in force on that date
+    pub fn date_in(&self, year: i32) -> Option<NaiveDate> {
+        let date = MOVED_HOLIDAYS
+            .iter()
+            .find(|moved| moved.year == year && moved.name == self.name)
+            .map_or_else(
+                || self.date.date_in(year),
+                |moved| NaiveDate::from_ymd_opt(year, moved.month, moved.day),
+            )?;
+        self.effective.contains(date).then_some(date)
+    }
+}
+
+const fn rule(name: &'static str, date: DateRule, effective: Effective) -> HolidayRule {
+    HolidayRule {
+        name,
+        date,
+        effective,
+    }
+}
+
+const fn fixed(month: u32, day: u32) -> DateRule {
+    DateRule::Fixed { month, day }
+}
+
+const fn nth_monday(month: u32, n: u8) -> DateRule {
+    DateRule::NthMonday { month, n }
+}
+
+/// National holidays throughout the history of the Act
+pub const NATIONAL_HOLIDAY_RULES: &[HolidayRule] = &[
+    rule("元日", fixed(1, 1), Effective::from(ACT_EFFECTIVE)),
+    rule(
+        "成人の日",
+        fixed(1, 15),
+        Effective::between(ACT_EFFECTIVE, (2000, 1, 1)),
+    ),
+    rule("成人の日", nth_monday(1, 2), Effective::from((2000, 1, 1))),
+    rule("建国記念の日", fixed(2, 11), Effective::from((1966, 6, 25))),
+    rule("天皇誕生日", fixed(2, 23), Effective::from((2019, 5, 1))),
+    rule(
+        "春分の日",
+        DateRule::VernalEquinox,
+        Effective::from(ACT_EFFECTIVE),
+    ),
+    rule(
+        "天皇誕生日",
+        fixed(4, 29),
+        Effective::between(ACT_EFFECTIVE, (1989, 2, 17)),
+    ),
+    rule(
+        "みどりの日",
+        fixed(4, 29),
+        Effective::between((1989, 2, 17), (2007, 1, 1)),
+    ),
+    rule("昭和の日", fixed(4, 29), Effective::from((2007, 1, 1))),
+    rule("憲法記念日", fixed(5, 3), Effective::from(ACT_EFFECTIVE)),
+    rule("みどりの日", fixed(5, 4), Effective::from((2007, 1, 1))),
+    rule("こどもの日", fixed(5, 5), Effective::from(ACT_EFFECTIVE)),
+    rule(
+        "海の日",
+        fixed(7, 20),
+        Effective::between((1996, 1, 1), (2003, 1, 1)),
+    ),
+    rule("海の日", nth_monday(7, 3), Effective::from((2003, 1, 1))),
+    rule("山の日", fixed(8, 11), Effective::from((2016, 1, 1))),
+    rule(
+        "敬老の日",
+        fixed(9, 15),
+        Effective::between((1966, 6, 25), (2003, 1, 1)),
+    ),
+    rule("敬老の日", nth_monday(9, 3), Effective::from((2003, 1, 1))),
+    rule(
+        "秋分の日",
+        DateRule::AutumnalEquinox,
+        Effective::from(ACT_EFFECTIVE),
+    ),
+    rule(
+        "体育の日",
+        fixed(10, 10),
+        Effective::between((1966, 6, 25), (2000, 1, 1)),
+    ),
+    rule(
+        "体育の日",
+        nth_monday(10, 2),
+        Effective::between((2000, 1, 1), (2020, 1, 1)),
+    ),
+    rule(
+        "スポーツの日",
+        nth_monday(10, 2),
+        Effective::from((2020, 1, 1)),
+    ),
+    rule("文化の日", fixed(11, 3), Effective::from(ACT_EFFECTIVE)),
+    rule(
+        "勤労感謝の日",
+        fixed(11, 23),
+        Effective::from(ACT_EFFECTIVE),
+    ),
+    rule(
+        "天皇誕生日",
+        fixed(12, 23),
+        Effective::between((1989, 2, 17), (2019, 5, 1)),
+    ),
+];
+
+/// A holiday moved to another date for a single year by a special measure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovedHoliday {
+    pub year: i32,
+    pub name: &'static str,
+    pub month: u32,
+    pub day: u32,
+}
+
+/// Holidays moved for the Tokyo Olympic and Paralympic Games
+pub const MOVED_HOLIDAYS: &[MovedHoliday] = &[
+    MovedHoliday {
+        year: 2020,
+        name: "海の日",
+        month: 7,
+        day: 23,
+    },
+    MovedHoliday {
+        year: 2020,
+        name: "スポーツの日",
+        month: 7,
+        day: 24,
+    },
+    MovedHoliday {
+        year: 2020,
+        name: "山の日",
+        month: 8,
+        day: 10,
+    },
+    MovedHoliday {
+        year: 2021,
+        name: "海の日",
+        month: 7,
+        day: 22,
+    },
+    MovedHoliday {
+        year: 2021,
+        name: "スポーツの日",
+        month: 7,
+        day: 23,
+    },
+    MovedHoliday {
+        year: 2021,
+        name: "山の日",
+        month: 8,
+        day: 8,
+    },
+];
+
+/// One-off holidays designated by special laws for imperial ceremonies,
+/// named as in the Cabinet Office CSV
+pub const SPECIAL_HOLIDAYS: &[(Ymd, &str)] = &[
+    ((1959, 4, 10), "結婚ノ儀"),
+    ((1989, 2, 24), "大喪の礼"),
+    ((1990, 11, 12), "即位礼正殿の儀"),
+    ((1993, 6, 9), "結婚ノ儀"),
+    ((2019, 5, 1), "休日（祝日扱い）"),
+    ((2019, 10, 22), "休日（祝日扱い）"),
+];
+
+/// Name of the one-off special holiday on `date`, if any
+pub fn special_holiday_name(date: NaiveDate) -> Option<&'static str> {
+    let ymd = (date.year(), date.month(), date.day());
+    SPECIAL_HOLIDAYS
+        .iter()
+        .find(|(special, _)| *special == ymd)
+        .map(|(_, name)| *name)
+}
+
+/// How a national holiday falling on Sunday is compensated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubstituteRule {
+    /// No substitute holiday
+    None,
+    /// The following Monday, unless it is a national holiday itself (1973 amendment)
+    NextDay,
+    /// The nearest following day that is not a national holiday (2005 amendment)
+    NextNonHoliday,
+}
+
+/// When a day sandwiched between two national holidays becomes a holiday
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitizensRule {
+    /// No citizens' holiday
+    None,
+    /// Any such day except Sundays and substitute holidays (1985 amendment)
+    ExceptSundays,
+    /// Any such day that is not a national holiday (2005 amendment)
+    Any,
+}
+
+/// A revision of the Act changing how substitute and citizens' holidays work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LawRevision {
+    pub effective: Ymd,
+    pub substitute: SubstituteRule,
+    pub citizens: CitizensRule,
+}
+
+/// Revisions of the Act in effective-date order
+pub const LAW_REVISIONS: &[LawRevision] = &[
+    LawRevision {
+        effective: ACT_EFFECTIVE,
+        substitute: SubstituteRule::None,
+        citizens: CitizensRule::None,
+    },
+    LawRevision {
+        effective: (1973, 4, 12),
+        substitute: SubstituteRule::NextDay,
+        citizens: CitizensRule::None,
+    },
+    LawRevision {
+        effective: (1985, 12, 27),
+        substitute: SubstituteRule::NextDay,
+        citizens: CitizensRule::ExceptSundays,
+    },
+    LawRevision {
+        effective: (2007, 1, 1),
+        substitute: SubstituteRule::NextNonHoliday,
+        citizens: CitizensRule::Any,
+    },
+];
+
+/// The revision of the Act in force on `date`, or `None` before the Act took effect
+pub fn revision_on(date: NaiveDate) -> Option<&'static LawRevision> {
+    let ymd = (date.year(), date.month(), date.day());
+    LAW_REVISIONS
+        .iter()
+        .rev()
+        .find(|revision| revision.effective <= ymd)
+}
+
+/// Day of March of the vernal equinox, using the approximation published by
+/// the National Astronomical Observatory of Japan (valid for 1900-2150)
+pub fn vernal_equinox_day(year: i32) -> Option<u32> {
+    equinox_day(year, 20.8357, 20.8431, 21.8510)
+}
+
+/// Day of September of the autumnal equinox (valid for 1900-2150)
+pub fn autumnal_equinox_day(year: i32) -> Option<u32> {
+    equinox_day(year, 23.2588, 23.2488, 24.2488)
+}
+
+fn equinox_day(year: i32, base_1900: f64, base_1980: f64, base_2100: f64) -> Option<u32> {
+    let (base, leap_offset) = match year {
+        1900..=1979 => (base_1900, (year - 1983) / 4),
+        1980..=2099 => (base_1980, (year - 1980) / 4),
+        2100..=2150 => (base_2100, (year - 1980) / 4),
+        _ => return None,
+    };
+    let day = base + 0.242194 * f64::from(year - 1980) - f64::from(leap_offset);
+    Some(day as u32)
+}
+
+/// Compute all holidays of a year as the law stood on each date, including
+/// substitute holidays (振替休日) and citizens' holidays (国民の休日), in date order
+pub fn holidays_in_year(year: i32) -> Vec<Holiday> {
+    let mut holidays: BTreeMap<NaiveDate, Holiday> = NATIONAL_HOLIDAY_RULES
+        .iter()
+        .filter_map(|rule| {
+            rule.date_in(year)
+                .map(|date| (date, Holiday::new(date, rule.name, HolidayKind::National)))
+        })
+        .collect();
+
+    for &((y, month, day), name) in SPECIAL_HOLIDAYS {
+        if let Some(date) = NaiveDate::from_ymd_opt(y, month, day).filter(|_| y == year) {
+            holidays.insert(date, Holiday::new(date, name, HolidayKind::Special));
+        }
+    }
+
+    // Special holidays count as national holidays for the rules below
+    let national: Vec<NaiveDate> = holidays.keys().copied().collect();
+
+    // 振替休日
+    for &date in &national {
+        if date.weekday() != Weekday::Sun {
+            continue;
+        }
+        let rule = revision_on(date).map_or(SubstituteRule::None, |r| r.substitute);
+        let substitute = match rule {
+            SubstituteRule::None => continue,
+            SubstituteRule::NextDay => {
+                let next = date + Duration::days(1);
+                if holidays.contains_key(&next) {
+                    continue;
+                }
+                next
+            }
+            SubstituteRule::NextNonHoliday => {
+                let mut next = date + Duration::days(1);
+                while holidays.contains_key(&next) {
+                    next += Duration::days(1);
+                }
+                next
+            }
+        };
+        if substitute.year() == year {
+            holidays.insert(
+                substitute,
+                Holiday::new(substitute, GENERIC_HOLIDAY_NAME, HolidayKind::Substitute),
+            );
+        }
+    }
+
+    // 国民の休日
+    for pair in national.windows(2) {
+        let (before, after) = (pair[0], pair[1]);
+        if after - before != Duration::days(2) {
+            continue;
+        }
+        let between = before + Duration::days(1);
+        let applies = match revision_on(between).map_or(CitizensRule::None, |r| r.citizens) {
+            CitizensRule::None => false,
+            CitizensRule::ExceptSundays => between.weekday() != Weekday::Sun,
+            CitizensRule::Any => true,
+        };
+        if applies {
+            holidays.entry(between).or_insert_with(|| {
+                Holiday::new(between, GENERIC_HOLIDAY_NAME, HolidayKind::Citizens)
+            });
+        }
+    }
+
+    holidays
+        .into_values()
+        .map(|holiday| holiday.with_origin(HolidayOrigin::Computed))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_equinox_days() {
+        let cases = [
+            (1955, 21, 24),
+            (1960, 20, 23),
+            (1979, 21, 24),
+            (2012, 20, 22),
+            (2023, 21, 23),
+            (2024, 20, 22),
+            (2025, 20, 23),
+        ];
+        for (year, vernal, autumnal) in cases {
+            assert_eq!(vernal_equinox_day(year), Some(vernal), "vernal {}", year);
+            assert_eq!(
+                autumnal_equinox_day(year),
+                Some(autumnal),
+                "autumnal {}",
+                year
+            );
+        }
+        assert_eq!(vernal_equinox_day(2151), None);
+    }
+
+    #[test]
+    fn test_holidays_in_year_2023() {
+        let holidays = holidays_in_year(2023);
+        let dates: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+
+        assert_eq!(holidays.len(), 17);
+        assert!(dates.contains(&date(2023, 1, 9))); // 成人の日
+        assert!(dates.contains(&date(2023, 3, 21))); // 春分の日
+        assert!(dates.contains(&date(2023, 10, 9))); // スポーツの日
+
+        let substitute = holidays
+            .iter()
+            .find(|h| h.date == date(2023, 1, 2))
+            .unwrap();
+        assert_eq!(substitute.kind, HolidayKind::Substitute);
+    }
+
+    #[test]
+    fn test_substitute_skips_consecutive_holidays() {
+        // 2025-05-04 (みどりの日) is a Sunday; 5/5 is こどもの日, so 5/6 is the substitute
+        let holidays = holidays_in_year(2025);
+        let substitute = holidays
+            .iter()
+            .find(|h| h.date == date(2025, 5, 6))
+            .unwrap();
+        assert_eq!(substitute.kind, HolidayKind::Substitute);
+    }
+
+    fn names_in_year(year: i32) -> Vec<(NaiveDate, String)> {
+        holidays_in_year(year)
+            .into_iter()
+            .map(|h| (h.date, h.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_no_holidays_before_the_act() {
+        assert!(holidays_in_year(1947).is_empty());
+        let dates: Vec<NaiveDate> = holidays_in_year(1948).iter().map(|h| h.date).collect();
+        assert_eq!(
+            dates,
+            vec![date(1948, 9, 23), date(1948, 11, 3), date(1948, 11, 23)]
+        );
+    }
+
+    #[test]
+    fn test_renamed_and_moved_holidays() {
+        let names_1988 = names_in_year(1988);
+        assert!(names_1988.contains(&(date(1988, 4, 29), "天皇誕生日".to_string())));
+        assert!(names_1988.contains(&(date(1988, 1, 15), "成人の日".to_string())));
+
+        let names_1989 = names_in_year(1989);
+        assert!(names_1989.contains(&(date(1989, 2, 24), "大喪の礼".to_string())));
+        assert!(names_1989.contains(&(date(1989, 4, 29), "みどりの日".to_string())));
+        assert!(names_1989.contains(&(date(1989, 12, 23), "天皇誕生日".to_string())));
+
+        // Happy Monday: 体育の日 in 2000 and 海の日 in 2003
+        assert!(names_in_year(2000).contains(&(date(2000, 10, 9), "体育の日".to_string())));
+        assert!(names_in_year(2003).contains(&(date(2003, 7, 21), "海の日".to_string())));
+
+        // Tokyo Olympics
+        let names_2021 = names_in_year(2021);
+        assert!(names_2021.contains(&(date(2021, 7, 23), "スポーツの日".to_string())));
+        assert!(names_2021.contains(&(date(2021, 8, 9), "休日".to_string())));
+        assert!(!names_2021.iter().any(|(d, _)| *d == date(2021, 10, 11)));
+    }
+
+    #[test]
+    fn test_enthronement_year() {
+        let names = names_in_year(2019);
+        assert_eq!(names.len(), 22);
+        assert!(!names.iter().any(|(_, n)| n == "天皇誕生日"));
+
+        let holidays = holidays_in_year(2019);
+        let kind_on = |d| holidays.iter().find(|h| h.date == d).unwrap().kind;
+        assert_eq!(kind_on(date(2019, 4, 30)), HolidayKind::Citizens);
+        assert_eq!(kind_on(date(2019, 5, 1)), HolidayKind::Special);
+        assert_eq!(kind_on(date(2019, 5, 2)), HolidayKind::Citizens);
+        assert_eq!(kind_on(date(2019, 5, 6)), HolidayKind::Substitute);
+    }
+
+    #[test]
+    fn test_substitute_rule_versions() {
+        // Before 1973 a holiday on Sunday (1967-01-01) was simply lost
+        assert!(!names_in_year(1967)
+            .iter()
+            .any(|(d, _)| *d == date(1967, 1, 2)));
+        // First substitute holiday under the 1973 amendment
+        assert!(names_in_year(1973).contains(&(date(1973, 4, 30), "休日".to_string())));
+        // みどりの日 on Sunday 2008-05-04 is carried past こどもの日 under the 2005 amendment
+        assert!(names_in_year(2008).contains(&(date(2008, 5, 6), "休日".to_string())));
+    }
+
+    #[test]
+    fn test_citizens_holiday() {
+        // 2026-09-21 is 敬老の日 and 2026-09-23 is 秋分の日
+        let holidays = holidays_in_year(2026);
+        let citizens = holidays
+            .iter()
+            .find(|h| h.date == date(2026, 9, 22))
+            .unwrap();
+        assert_eq!(citizens.kind, HolidayKind::Citizens);
+    }
+}
@@ -0,0 +1,276 @@
+//! Sanity checks on downloaded data before it replaces the cache.
+//!
+//! A broken download (an HTML error page, a truncated file, a feed missing whole
+//! years) still parses into some holiday map. These invariants catch that, and the
+//! cache keeps the previous data when any of them fails.
+
+use crate::parse::ParseReport;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Which invariants are checked (`[cache.sanity]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanityConfig {
+    /// Fewest holidays a year in the data may have; 1955 had 9. 0 disables the check
+    pub min_holidays_per_year: usize,
+    /// Every year in the cache must still be in the data, with at least as many holidays
+    pub no_year_regressions: bool,
+    /// The data must include the current year
+    pub require_current_year: bool,
+    /// The CSV must not list a date twice
+    pub unique_dates: bool,
+    /// Fewest holidays the data may have as a share of the cached ones. 0 disables the check
+    pub min_replacement_ratio: f64,
+}
+
+impl Default for SanityConfig {
+    fn default() -> Self {
+        Self {
+            min_holidays_per_year: 9,
+            no_year_regressions: true,
+            require_current_year: true,
+            unique_dates: true,
+            min_replacement_ratio: 0.9,
+        }
+    }
+}
+
+impl SanityConfig {
+    /// Only refuse data without any holidays
+    pub fn disabled() -> Self {
+        Self {
+            min_holidays_per_year: 0,
+            no_year_regressions: false,
+            require_current_year: false,
+            unique_dates: false,
+            min_replacement_ratio: 0.0,
+        }
+    }
+}
+
+/// A failed invariant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SanityViolation {
+    /// The data has no holidays at all
+    Empty,
+    /// A year has fewer holidays than `min_holidays_per_year`
+    TooFewInYear {
+        year: i32,
+        count: usize,
+        minimum: usize,
+    },
+    /// A year has fewer holidays than in the cache, or is missing
+    YearRegression {
+        year: i32,
+        previous: usize,
+        current: usize,
+    },
+    /// The current year is not covered
+    MissingCurrentYear { year: i32 },
+    /// The CSV lists dates more than once
+    DuplicateDates { count: usize },
+    /// The data is much smaller than the cache
+    Shrunk { previous: usize, current: usize },
+}
+
+impl fmt::Display for SanityViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanityViolation::Empty => write!(f, "no holidays"),
+            SanityViolation::TooFewInYear {
+                year,
+                count,
+                minimum,
+            } => write!(f, "{} has {} holidays, fewer than {}", year, count, minimum),
+            SanityViolation::YearRegression {
+                year,
+                previous,
+                current,
+            } => write!(
+                f,
+                "{} has {} holidays, against {} in the cache",
+                year, current, previous
+            ),
+            SanityViolation::MissingCurrentYear { year } => {
+                write!(f, "the current year {} is missing", year)
+            }
+            SanityViolation::DuplicateDates { count } => {
+                write!(f, "{} dates are listed more than once", count)
+            }
+            SanityViolation::Shrunk { previous, current } => write!(
+                f,
+                "only {} holidays, against {} in the cache",
+                current, previous
+            ),
+        }
+    }
+}
+
+/// Downloaded data failed the sanity checks; the previous data was kept.
+/// Returned inside `anyhow::Error`, so use `error.downcast_ref::<SanityError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanityError {
+    pub violations: Vec<SanityViolation>,
+}
+
+impl fmt::Display for SanityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let violations: Vec<String> = self.violations.iter().map(|v| v.to_string()).collect();
+        write!(
+            f,
+            "Downloaded data failed the sanity checks ({}); keeping the cache",
+            violations.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for SanityError {}
+
+/// Check `holidays` before it replaces `previous`
+pub fn check(
+    config: &SanityConfig,
+    holidays: &HashMap<NaiveDate, String>,
+    report: Option<&ParseReport>,
+    previous: Option<&HashMap<NaiveDate, String>>,
+    today: NaiveDate,
+) -> Result<(), SanityError> {
+    if holidays.is_empty() {
+        return Err(SanityError {
+            violations: vec![SanityViolation::Empty],
+        });
+    }
+
+    let mut violations = Vec::new();
+    let per_year = count_per_year(holidays);
+
+    if config.min_holidays_per_year > 0 {
+        violations.extend(per_year.iter().filter_map(|(&year, &count)| {
+            (count < config.min_holidays_per_year).then_some(SanityViolation::TooFewInYear {
+                year,
+                count,
+                minimum: config.min_holidays_per_year,
+            })
+        }));
+    }
+
+    if config.require_current_year && !per_year.contains_key(&today.year()) {
+        violations.push(SanityViolation::MissingCurrentYear { year: today.year() });
+    }
+
+    if config.unique_dates {
+        if let Some(report) = report.filter(|report| report.duplicates > 0) {
+            violations.push(SanityViolation::DuplicateDates {
+                count: report.duplicates,
+            });
+        }
+    }
+
+    if let Some(previous) = previous {
+        if config.no_year_regressions {
+            for (year, previous_count) in count_per_year(previous) {
+                let current = per_year.get(&year).copied().unwrap_or(0);
+                if current < previous_count {
+                    violations.push(SanityViolation::YearRegression {
+                        year,
+                        previous: previous_count,
+                        current,
+                    });
+                }
+            }
+        }
+
+        let minimum = (previous.len() as f64 * config.min_replacement_ratio).ceil() as usize;
+        if holidays.len() < minimum {
+            violations.push(SanityViolation::Shrunk {
+                previous: previous.len(),
+                current: holidays.len(),
+            });
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(SanityError { violations })
+    }
+}
+
+fn count_per_year(holidays: &HashMap<NaiveDate, String>) -> BTreeMap<i32, usize> {
+    let mut per_year = BTreeMap::new();
+    for date in holidays.keys() {
+        *per_year.entry(date.year()).or_insert(0) += 1;
+    }
+    per_year
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules;
+
+    fn years(range: std::ops::RangeInclusive<i32>) -> HashMap<NaiveDate, String> {
+        range
+            .flat_map(rules::holidays_in_year)
+            .map(|holiday| (holiday.date, holiday.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_invariants() {
+        let config = SanityConfig::default();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let good = years(1955..=2025);
+        assert_eq!(check(&config, &good, None, Some(&good), today), Ok(()));
+
+        assert_eq!(
+            check(&config, &HashMap::new(), None, None, today)
+                .unwrap_err()
+                .violations,
+            vec![SanityViolation::Empty]
+        );
+
+        // A feed that lost its last two years
+        let truncated = years(1955..=2023);
+        let violations = check(&config, &truncated, None, Some(&good), today)
+            .unwrap_err()
+            .violations;
+        assert!(violations.contains(&SanityViolation::MissingCurrentYear { year: 2024 }));
+        assert!(violations.iter().any(|v| matches!(
+            v,
+            SanityViolation::YearRegression {
+                year: 2025,
+                current: 0,
+                ..
+            }
+        )));
+
+        let report = ParseReport {
+            duplicates: 2,
+            ..ParseReport::default()
+        };
+        assert_eq!(
+            check(&config, &good, Some(&report), None, today)
+                .unwrap_err()
+                .violations,
+            vec![SanityViolation::DuplicateDates { count: 2 }]
+        );
+
+        let mut partial = years(2024..=2024);
+        partial.retain(|date, _| date.month() < 3);
+        assert!(check(&config, &partial, None, None, today).is_err());
+        assert_eq!(
+            check(
+                &SanityConfig::disabled(),
+                &partial,
+                None,
+                Some(&good),
+                today
+            ),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,584 @@
+//! Providers of holiday data.
+//!
+//! A `HolidaySource` returns the full date -> name map in one `fetch`, and can
+//! describe the version it would return without fetching it, so `HolidayCache`
+//! can decide whether a refresh is needed.
+
+use crate::config::{HolidayDataConfig, HttpConfig};
+use crate::parse::{self, ParseReport};
+use anyhow::{Context, Result};
+#[cfg(feature = "embedded")]
+use chrono::TimeZone;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(feature = "fetch")]
+use std::sync::OnceLock;
+#[cfg(feature = "fetch")]
+use std::time::Duration;
+
+/// Version information about the data a source would return
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMetadata {
+    pub etag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// The data returned by `HolidaySource::fetch`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceData {
+    pub holidays: HashMap<NaiveDate, String>,
+    pub metadata: SourceMetadata,
+    /// How the CSV was parsed, for sources that parse one
+    pub report: Option<ParseReport>,
+}
+
+impl SourceData {
+    fn from_csv(bytes: &[u8], metadata: SourceMetadata) -> Result<Self> {
+        let parsed = parse::parse_bytes(bytes)?;
+        Ok(Self {
+            holidays: parsed.holidays,
+            metadata,
+            report: Some(parsed.report),
+        })
+    }
+}
+
+pub trait HolidaySource: Send + Sync {
+    /// Where the data comes from, for messages
+    fn describe(&self) -> String;
+
+    /// Fetch the full data set
+    fn fetch(&self) -> Result<SourceData>;
+
+    /// Version information of the current data, without fetching it where possible
+    fn metadata(&self) -> Result<SourceMetadata>;
+
+    /// Fetch the data unless it is unchanged since the version described by `cached`,
+    /// in which case `None` is returned
+    fn fetch_if_modified(&self, cached: &SourceMetadata) -> Result<Option<SourceData>> {
+        match self.is_modified(cached)? {
+            Some(false) => Ok(None),
+            _ => self.fetch().map(Some),
+        }
+    }
+
+    /// Whether the data changed since the version described by `cached`;
+    /// `None` when the metadata is not enough to tell
+    fn is_modified(&self, cached: &SourceMetadata) -> Result<Option<bool>> {
+        let current = self.metadata()?;
+        Ok(match (&current.etag, &cached.etag) {
+            (Some(current), Some(cached)) => Some(current != cached),
+            _ => match (current.last_modified, cached.last_modified) {
+                (Some(current), Some(cached)) => Some(current > cached),
+                _ => None,
+            },
+        })
+    }
+}
+
+/// The source for `holiday_data.source_url`, downloaded with `holiday_data.http`
+pub fn from_config(config: &HolidayDataConfig) -> Arc<dyn HolidaySource> {
+    from_url_with(&config.source_url, &config.http)
+}
+
+/// The source for a URL: HTTP(S) URLs are downloaded with the default settings,
+/// anything else is read as a local file (an optional `file://` prefix is stripped)
+pub fn from_url(url: &str) -> Arc<dyn HolidaySource> {
+    from_url_with(url, &HttpConfig::default())
+}
+
+#[cfg_attr(not(feature = "fetch"), allow(unused_variables))]
+fn from_url_with(url: &str, http: &HttpConfig) -> Arc<dyn HolidaySource> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        #[cfg(feature = "fetch")]
+        return Arc::new(HttpSource::new(url).with_config(http.clone()));
+        #[cfg(not(feature = "fetch"))]
+        return Arc::new(Unavailable {
+            url: url.to_string(),
+        });
+    }
+    Arc::new(FileSource::new(url.strip_prefix("file://").unwrap_or(url)))
+}
+
+/// A holiday CSV in the Cabinet Office format on the local file system
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl HolidaySource for FileSource {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn fetch(&self) -> Result<SourceData> {
+        let metadata = self.metadata()?;
+        let bytes = std::fs::read(&self.path)
+            .with_context(|| format!("Failed to read CSV file: {}", self.path.display()))?;
+        SourceData::from_csv(&bytes, metadata)
+    }
+
+    fn metadata(&self) -> Result<SourceMetadata> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("Failed to read CSV file: {}", self.path.display()))?;
+        Ok(SourceMetadata {
+            etag: None,
+            last_modified: Some(modified.into()),
+        })
+    }
+}
+
+/// Holidays held in memory, e.g. test fixtures
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    holidays: HashMap<NaiveDate, String>,
+    metadata: SourceMetadata,
+}
+
+impl MemorySource {
+    pub fn new(holidays: HashMap<NaiveDate, String>) -> Self {
+        Self {
+            holidays,
+            metadata: SourceMetadata::default(),
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: SourceMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+impl HolidaySource for MemorySource {
+    fn describe(&self) -> String {
+        "in-memory holidays".to_string()
+    }
+
+    fn fetch(&self) -> Result<SourceData> {
+        Ok(SourceData {
+            holidays: self.holidays.clone(),
+            metadata: self.metadata.clone(),
+            report: None,
+        })
+    }
+
+    fn metadata(&self) -> Result<SourceMetadata> {
+        Ok(self.metadata.clone())
+    }
+}
+
+/// The snapshot compiled in with the `embedded` feature
+#[cfg(feature = "embedded")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmbeddedSource;
+
+#[cfg(feature = "embedded")]
+impl HolidaySource for EmbeddedSource {
+    fn describe(&self) -> String {
+        format!("embedded snapshot of {}", crate::embedded::SNAPSHOT_DATE)
+    }
+
+    fn fetch(&self) -> Result<SourceData> {
+        SourceData::from_csv(crate::embedded::SNAPSHOT_CSV, self.metadata()?)
+    }
+
+    fn metadata(&self) -> Result<SourceMetadata> {
+        let snapshot_date = crate::embedded::snapshot_date();
+        Ok(SourceMetadata {
+            etag: Some(format!("embedded-{}", snapshot_date)),
+            last_modified: snapshot_date
+                .and_hms_opt(0, 0, 0)
+                .map(|time| Utc.from_utc_datetime(&time)),
+        })
+    }
+}
+
+/// IMF-fixdate, as used by `Last-Modified` and `If-Modified-Since`
+#[cfg(feature = "fetch")]
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// The Cabinet Office CSV (or a mirror) downloaded over HTTP
+///
+/// Requests block, so call it from a blocking context (`HolidayCache::get_holidays`
+/// does this with `spawn_blocking`).
+#[cfg(feature = "fetch")]
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    url: String,
+    config: HttpConfig,
+    /// Created by the first request and reused by the following ones and their retries
+    client: OnceLock<reqwest::blocking::Client>,
+}
+
+#[cfg(feature = "fetch")]
+impl HttpSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            config: HttpConfig::default(),
+            client: OnceLock::new(),
+        }
+    }
+
+    /// Use the timeouts, retries, proxy and certificates of `config`
+    pub fn with_config(mut self, config: HttpConfig) -> Self {
+        self.config = config;
+        self.client = OnceLock::new();
+        self
+    }
+
+    /// The blocking client runs its own runtime and must not be created inside an async
+    /// one, so it is only created by the first request, which blocks anyway
+    fn client(&self) -> Result<&reqwest::blocking::Client> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = self.build_client()?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    /// The timeouts are set on each request
+    fn build_client(&self) -> Result<reqwest::blocking::Client> {
+        let user_agent = self
+            .config
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| format!("holidays_jp/{}", env!("CARGO_PKG_VERSION")));
+        let mut builder = reqwest::blocking::Client::builder().user_agent(user_agent);
+
+        if let Some(proxy) = &self.config.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .with_context(|| format!("Invalid proxy URL: {}", proxy))?;
+            builder = builder.proxy(proxy);
+        }
+        for path in &self.config.ca_certificates {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read CA certificate: {}", path))?;
+            let certificate = reqwest::Certificate::from_pem(&pem)
+                .with_context(|| format!("Invalid CA certificate: {}", path))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        builder.build().context("Failed to create HTTP client")
+    }
+
+    /// Send the request built by `request`, retrying connection errors, timeouts,
+    /// 429 and 5xx responses with exponential backoff
+    fn send(
+        &self,
+        request: impl Fn(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response> {
+        let client = self.client()?;
+        let mut attempt = 0;
+        loop {
+            let result = request(client).send();
+            let retryable = match &result {
+                Ok(response) => {
+                    let status = response.status();
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(error) => error.is_connect() || error.is_timeout(),
+            };
+            if !retryable || attempt >= self.config.retries {
+                // reqwest errors already name the URL
+                return result.map_err(Into::into);
+            }
+            std::thread::sleep(self.backoff(attempt));
+            attempt += 1;
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .config
+            .backoff_ms
+            .saturating_mul(1 << attempt.min(20))
+            .min(self.config.max_backoff_ms);
+        Duration::from_millis(delay)
+    }
+
+    fn read_response(response: reqwest::blocking::Response) -> Result<SourceData> {
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to download data: {}",
+                response.status()
+            ));
+        }
+
+        let metadata = Self::response_metadata(&response);
+        SourceData::from_csv(&response.bytes()?, metadata)
+    }
+
+    fn response_metadata(response: &reqwest::blocking::Response) -> SourceMetadata {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        SourceMetadata {
+            etag: header(reqwest::header::ETAG).map(|s| s.to_string()),
+            last_modified: header(reqwest::header::LAST_MODIFIED)
+                .and_then(|s| DateTime::parse_from_rfc2822(s).ok())
+                .map(|time| time.with_timezone(&Utc)),
+        }
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl HolidaySource for HttpSource {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn fetch(&self) -> Result<SourceData> {
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let response = self.send(|client| client.get(&self.url).timeout(timeout))?;
+        Self::read_response(response)
+    }
+
+    /// A single GET with `If-None-Match` / `If-Modified-Since`, limited to
+    /// `check_timeout_secs`; `304 Not Modified` gives `None`
+    fn fetch_if_modified(&self, cached: &SourceMetadata) -> Result<Option<SourceData>> {
+        let timeout = Duration::from_secs(self.config.check_timeout_secs);
+        let response = self.send(|client| {
+            let mut request = client.get(&self.url).timeout(timeout);
+            if let Some(etag) = &cached.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = cached.last_modified {
+                request = request.header(
+                    reqwest::header::IF_MODIFIED_SINCE,
+                    last_modified.format(HTTP_DATE_FORMAT).to_string(),
+                );
+            }
+            request
+        })?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        Self::read_response(response).map(Some)
+    }
+
+    fn metadata(&self) -> Result<SourceMetadata> {
+        let timeout = Duration::from_secs(self.config.check_timeout_secs);
+        let response = self.send(|client| client.head(&self.url).timeout(timeout))?;
+
+        if response.status().is_success() {
+            Ok(Self::response_metadata(&response))
+        } else {
+            Err(anyhow::anyhow!("HTTP error: {}", response.status()))
+        }
+    }
+
+    /// Only the ETag is trusted; without one the cache falls back to its age
+    fn is_modified(&self, cached: &SourceMetadata) -> Result<Option<bool>> {
+        if cached.etag.is_none() {
+            return Ok(None);
+        }
+        let current = self.metadata()?;
+        Ok(current.etag.map(|etag| Some(&etag) != cached.etag.as_ref()))
+    }
+}
+
+/// Stands in for an HTTP source when the `fetch` feature is disabled
+#[cfg(not(feature = "fetch"))]
+struct Unavailable {
+    url: String,
+}
+
+#[cfg(not(feature = "fetch"))]
+impl HolidaySource for Unavailable {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn fetch(&self) -> Result<SourceData> {
+        Err(anyhow::anyhow!(
+            "Downloading {} requires the `fetch` feature",
+            self.url
+        ))
+    }
+
+    fn metadata(&self) -> Result<SourceMetadata> {
+        self.fetch().map(|data| data.metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_file_and_memory_sources() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let file = dir.child("syukujitsu.csv");
+        file.write_str("国民の祝日・休日月日,国民の祝日・休日名称\n2023/1/1,元日\n")
+            .unwrap();
+
+        let source = from_url(&format!("file://{}", file.path().display()));
+        let data = source.fetch().unwrap();
+        assert_eq!(data.holidays[&date(2023, 1, 1)], "元日");
+        let fetched = data.metadata;
+        assert_eq!(source.is_modified(&fetched).unwrap(), Some(false));
+        assert!(FileSource::new(dir.child("missing.csv").path())
+            .fetch()
+            .is_err());
+
+        let memory = MemorySource::new([(date(2023, 1, 1), "元日".to_string())].into())
+            .with_metadata(SourceMetadata {
+                etag: Some("\"v2\"".to_string()),
+                last_modified: None,
+            });
+        let v1 = SourceMetadata {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        assert_eq!(memory.is_modified(&v1).unwrap(), Some(true));
+        assert_eq!(
+            memory.is_modified(&SourceMetadata::default()).unwrap(),
+            None
+        );
+    }
+
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn test_http_conditional_fetch() {
+        use chrono::TimeZone;
+        use httptest::{all_of, matchers::*, responders::*, Expectation, Server};
+
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/syukujitsu.csv"),
+                request::headers(not(contains(key("if-none-match")))),
+            ])
+            .respond_with(
+                status_code(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .insert_header("Last-Modified", "Mon, 02 Jan 2023 00:00:00 GMT")
+                    .body("国民の祝日・休日月日,国民の祝日・休日名称\n2023/1/1,元日\n"),
+            ),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/syukujitsu.csv"),
+                request::headers(contains(("if-none-match", "\"v1\""))),
+                request::headers(contains((
+                    "if-modified-since",
+                    "Mon, 02 Jan 2023 00:00:00 GMT"
+                ))),
+            ])
+            .respond_with(status_code(304)),
+        );
+
+        let source = HttpSource::new(&server.url_str("/syukujitsu.csv"));
+        let data = source.fetch().unwrap();
+        assert_eq!(data.holidays[&date(2023, 1, 1)], "元日");
+        assert_eq!(data.metadata.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            data.metadata.last_modified,
+            Some(Utc.from_utc_datetime(&date(2023, 1, 2).and_hms_opt(0, 0, 0).unwrap()))
+        );
+
+        let client: *const reqwest::blocking::Client = source.client().unwrap();
+        assert_eq!(source.fetch_if_modified(&data.metadata).unwrap(), None);
+        // Both requests went through one client
+        assert!(std::ptr::eq(client, source.client().unwrap()));
+    }
+
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn test_http_update_check_uses_check_timeout() {
+        use httptest::{matchers::*, responders::*, Expectation, Server};
+
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/slow.csv"))
+                .times(2)
+                .respond_with(delay_and_then(
+                    std::time::Duration::from_secs(2),
+                    status_code(200)
+                        .body("国民の祝日・休日月日,国民の祝日・休日名称\n2023/1/1,元日\n"),
+                )),
+        );
+
+        let source = HttpSource::new(&server.url_str("/slow.csv")).with_config(HttpConfig {
+            check_timeout_secs: 1,
+            retries: 0,
+            ..HttpConfig::default()
+        });
+        // The update check gives up after `check_timeout_secs`, a download waits longer
+        assert!(source
+            .fetch_if_modified(&SourceMetadata::default())
+            .is_err());
+        assert_eq!(source.fetch().unwrap().holidays.len(), 1);
+    }
+
+    #[cfg(feature = "fetch")]
+    #[test]
+    fn test_http_retries_and_settings() {
+        use httptest::{all_of, cycle, matchers::*, responders::*, Expectation, Server};
+
+        let config = HttpConfig {
+            retries: 2,
+            backoff_ms: 1,
+            user_agent: Some("holidays-test/1.0".to_string()),
+            ..HttpConfig::default()
+        };
+
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/flaky.csv"),
+                request::headers(contains(("user-agent", "holidays-test/1.0"))),
+            ])
+            .times(3)
+            .respond_with(cycle![
+                status_code(503),
+                status_code(429),
+                status_code(200).body("国民の祝日・休日月日,国民の祝日・休日名称\n2023/1/1,元日\n"),
+            ]),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/down.csv"))
+                .times(3)
+                .respond_with(status_code(500)),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/missing.csv"))
+                .times(1)
+                .respond_with(status_code(404)),
+        );
+
+        let source =
+            |path: &str| HttpSource::new(&server.url_str(path)).with_config(config.clone());
+        assert_eq!(source("/flaky.csv").fetch().unwrap().holidays.len(), 1);
+        assert!(source("/down.csv").fetch().is_err());
+        assert!(source("/missing.csv").fetch().is_err());
+
+        let bad_proxy = HttpConfig {
+            proxy: Some("not a proxy url".to_string()),
+            ..HttpConfig::default()
+        };
+        let error = HttpSource::new(&server.url_str("/flaky.csv"))
+            .with_config(bad_proxy)
+            .fetch()
+            .unwrap_err();
+        assert!(error.to_string().contains("Invalid proxy URL"));
+    }
+}
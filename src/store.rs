@@ -0,0 +1,492 @@
+//! Where the holiday cache is persisted.
+//!
+//! `HolidayCache` decides when to refresh; a `CacheStore` only loads and saves
+//! the `CacheData`. The backend is chosen with `cache.backend`, and
+//! `holiday_data.cache_file` is the JSON file or the SQLite database.
+
+use crate::cache::{CacheData, CacheMetadata};
+use crate::config::{CacheBackend, Config};
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub trait CacheStore: Send + Sync {
+    /// Where the cache is kept, for messages
+    fn describe(&self) -> String;
+
+    /// The stored cache; `None` when nothing has been stored yet. Contents that
+    /// cannot be parsed are reported with a `CorruptCache` error.
+    fn load(&self) -> Result<Option<CacheData>>;
+
+    /// Replace the stored cache
+    fn save(&self, data: &CacheData) -> Result<()>;
+
+    /// Replace only the metadata of the stored cache, without rewriting the holidays,
+    /// e.g. after a check found no change; does nothing when nothing is stored
+    fn save_metadata(&self, metadata: &CacheMetadata) -> Result<()>;
+
+    /// Remove the stored cache, so the next load returns `None`
+    fn clear(&self) -> Result<()>;
+
+    /// Block until no other process is refreshing this cache; the lock is held
+    /// until the returned guard is dropped
+    fn lock_refresh(&self) -> Result<RefreshLock> {
+        Ok(RefreshLock::default())
+    }
+}
+
+/// The stored cache could not be parsed, e.g. a truncated file, and can only be replaced.
+/// Returned inside `anyhow::Error`, so use `error.downcast_ref::<CorruptCache>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptCache {
+    /// Where the cache is kept, from `CacheStore::describe`
+    pub location: String,
+    pub error: String,
+}
+
+impl fmt::Display for CorruptCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The cache at {} is corrupt: {}",
+            self.location, self.error
+        )
+    }
+}
+
+impl std::error::Error for CorruptCache {}
+
+/// Guard returned by `CacheStore::lock_refresh`
+#[derive(Debug, Default)]
+pub struct RefreshLock {
+    _file: Option<File>,
+}
+
+impl RefreshLock {
+    /// An advisory lock on `<path>.lock`, shared with every process using `path`
+    pub fn for_path(path: &Path) -> Result<Self> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+        if let Some(parent) = lock_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open lock file: {}", lock_path.display()))?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        Ok(Self { _file: Some(file) })
+    }
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it over `path`,
+/// so readers see either the old or the new file, never a partial one
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Not a file path: {}", path.display()))?;
+    let temp_path = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The store selected by `cache.backend`
+pub fn from_config(config: &Config) -> Arc<dyn CacheStore> {
+    let path = &config.holiday_data.cache_file;
+    match config.cache.backend {
+        CacheBackend::JsonFile => Arc::new(JsonFileStore::new(path)),
+        CacheBackend::Memory => Arc::new(MemoryStore::default()),
+        #[cfg(feature = "sqlite")]
+        CacheBackend::Sqlite => Arc::new(SqliteStore::new(path)),
+        #[cfg(not(feature = "sqlite"))]
+        CacheBackend::Sqlite => Arc::new(Unavailable),
+    }
+}
+
+/// A pretty-printed JSON file. Metadata updates go to a small `<path>.meta.json`
+/// next to it, so checks that find no change do not rewrite the holidays.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn metadata_path(&self) -> PathBuf {
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(".meta.json");
+        PathBuf::from(path)
+    }
+
+    /// Metadata saved by `save_metadata`; an unreadable file is ignored, as the
+    /// holiday file has metadata of its own
+    fn load_metadata(&self) -> Option<CacheMetadata> {
+        let content = std::fs::read_to_string(self.metadata_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn remove_metadata(&self) -> Result<()> {
+        match std::fs::remove_file(self.metadata_path()) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl CacheStore for JsonFileStore {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Option<CacheData>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&self.path).context("Failed to read cache file")?;
+        let mut cache_data: CacheData =
+            serde_json::from_str(&content).map_err(|error| CorruptCache {
+                location: self.describe(),
+                error: error.to_string(),
+            })?;
+        // Both are written with the current time, so the later one is the latest write
+        if let Some(metadata) = self.load_metadata() {
+            if metadata.last_updated >= cache_data.metadata.last_updated {
+                cache_data.metadata = metadata;
+            }
+        }
+        Ok(Some(cache_data))
+    }
+
+    fn save(&self, data: &CacheData) -> Result<()> {
+        let json = serde_json::to_string_pretty(data)?;
+        write_atomically(&self.path, json.as_bytes())?;
+        self.remove_metadata()
+    }
+
+    fn save_metadata(&self, metadata: &CacheMetadata) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(metadata)?;
+        write_atomically(&self.metadata_path(), json.as_bytes())
+    }
+
+    fn clear(&self) -> Result<()> {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        self.remove_metadata()
+    }
+
+    fn lock_refresh(&self) -> Result<RefreshLock> {
+        RefreshLock::for_path(&self.path)
+    }
+}
+
+/// Kept in memory only; clones share the same data
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    data: Arc<Mutex<Option<CacheData>>>,
+}
+
+impl MemoryStore {
+    fn data(&self) -> std::sync::MutexGuard<'_, Option<CacheData>> {
+        // The data is replaced as a whole, so a poisoned lock still holds a complete value
+        self.data
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn describe(&self) -> String {
+        "in-memory cache".to_string()
+    }
+
+    fn load(&self) -> Result<Option<CacheData>> {
+        Ok(self.data().clone())
+    }
+
+    fn save(&self, data: &CacheData) -> Result<()> {
+        *self.data() = Some(data.clone());
+        Ok(())
+    }
+
+    fn save_metadata(&self, metadata: &CacheMetadata) -> Result<()> {
+        if let Some(data) = self.data().as_mut() {
+            data.metadata = metadata.clone();
+        }
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        *self.data() = None;
+        Ok(())
+    }
+}
+
+/// An SQLite database (`sqlite` feature), which may be shared by several processes
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+    path: PathBuf,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn open(&self) -> Result<rusqlite::Connection> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(&self.path)
+            .with_context(|| format!("Failed to open cache database: {}", self.path.display()))?;
+        conn.busy_timeout(std::time::Duration::from_secs(10))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS cache_metadata (
+                 id INTEGER PRIMARY KEY CHECK (id = 1),
+                 last_updated TEXT NOT NULL,
+                 etag TEXT,
+                 last_etag_check TEXT,
+                 last_modified TEXT
+             );
+             CREATE TABLE IF NOT EXISTS holidays (
+                 date TEXT PRIMARY KEY,
+                 name TEXT NOT NULL
+             );",
+        )?;
+        Ok(conn)
+    }
+
+    /// Values that do not convert, e.g. a malformed date, make the cache corrupt
+    fn read_error(&self, error: rusqlite::Error) -> anyhow::Error {
+        match error {
+            rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..) => CorruptCache {
+                location: self.describe(),
+                error: error.to_string(),
+            }
+            .into(),
+            error => anyhow::Error::new(error).context("Failed to read cache database"),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl CacheStore for SqliteStore {
+    fn describe(&self) -> String {
+        format!("sqlite:{}", self.path.display())
+    }
+
+    fn load(&self) -> Result<Option<CacheData>> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.open()?;
+        let metadata = conn
+            .query_row(
+                "SELECT last_updated, etag, last_etag_check, last_modified
+                 FROM cache_metadata WHERE id = 1",
+                [],
+                |row| {
+                    Ok(CacheMetadata {
+                        last_updated: row.get(0)?,
+                        etag: row.get(1)?,
+                        last_etag_check: row.get(2)?,
+                        last_modified: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|error| self.read_error(error))?;
+        let Some(metadata) = metadata else {
+            return Ok(None);
+        };
+
+        let mut statement = conn.prepare("SELECT date, name FROM holidays")?;
+        let holidays = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(|error| self.read_error(error))?;
+        Ok(Some(CacheData { metadata, holidays }))
+    }
+
+    fn save(&self, data: &CacheData) -> Result<()> {
+        let mut conn = self.open()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM holidays", [])?;
+        {
+            let mut insert = tx.prepare("INSERT INTO holidays (date, name) VALUES (?1, ?2)")?;
+            for (date, name) in &data.holidays {
+                insert.execute(rusqlite::params![date, name])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO cache_metadata
+                 (id, last_updated, etag, last_etag_check, last_modified)
+             VALUES (1, ?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                data.metadata.last_updated,
+                data.metadata.etag,
+                data.metadata.last_etag_check,
+                data.metadata.last_modified
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_metadata(&self, metadata: &CacheMetadata) -> Result<()> {
+        self.open()?.execute(
+            "UPDATE cache_metadata
+             SET last_updated = ?1, etag = ?2, last_etag_check = ?3, last_modified = ?4
+             WHERE id = 1",
+            rusqlite::params![
+                metadata.last_updated,
+                metadata.etag,
+                metadata.last_etag_check,
+                metadata.last_modified
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.open()?
+            .execute_batch("DELETE FROM holidays; DELETE FROM cache_metadata;")?;
+        Ok(())
+    }
+
+    /// Saves are transactions already; the lock only keeps processes from downloading twice
+    fn lock_refresh(&self) -> Result<RefreshLock> {
+        RefreshLock::for_path(&self.path)
+    }
+}
+
+/// Stands in for the SQLite store when the `sqlite` feature is disabled
+#[cfg(not(feature = "sqlite"))]
+struct Unavailable;
+
+#[cfg(not(feature = "sqlite"))]
+impl CacheStore for Unavailable {
+    fn describe(&self) -> String {
+        "sqlite (unavailable)".to_string()
+    }
+
+    fn load(&self) -> Result<Option<CacheData>> {
+        Err(anyhow::anyhow!(
+            "The Sqlite cache backend requires the `sqlite` feature"
+        ))
+    }
+
+    fn save(&self, _data: &CacheData) -> Result<()> {
+        self.load().map(|_| ())
+    }
+
+    fn save_metadata(&self, _metadata: &CacheMetadata) -> Result<()> {
+        self.load().map(|_| ())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.load().map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+
+    fn cache_data() -> CacheData {
+        CacheData {
+            metadata: CacheMetadata {
+                last_updated: Utc::now(),
+                etag: Some("\"v1\"".to_string()),
+                last_etag_check: None,
+                last_modified: None,
+            },
+            holidays: [(
+                NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+                "元日".to_string(),
+            )]
+            .into(),
+        }
+    }
+
+    fn round_trip(store: &dyn CacheStore) {
+        assert!(store.load().unwrap().is_none());
+        let data = cache_data();
+        store.save(&data).unwrap();
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.holidays, data.holidays);
+        assert_eq!(loaded.metadata.etag, data.metadata.etag);
+        assert_eq!(loaded.metadata.last_updated, data.metadata.last_updated);
+
+        let mut metadata = data.metadata.clone();
+        metadata.last_etag_check = Some(Utc::now());
+        store.save_metadata(&metadata).unwrap();
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.metadata.last_etag_check, metadata.last_etag_check);
+        assert_eq!(loaded.holidays, data.holidays);
+
+        store.clear().unwrap();
+        assert!(store.load().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_file() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("holidays.json");
+        write_atomically(&path, b"old").unwrap();
+        write_atomically(&path, b"new").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1, "temporary files are renamed away");
+    }
+
+    #[test]
+    fn test_stores_round_trip() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        round_trip(&JsonFileStore::new(dir.path().join("cache/holidays.json")));
+        round_trip(&MemoryStore::default());
+        #[cfg(feature = "sqlite")]
+        round_trip(&SqliteStore::new(dir.path().join("holidays.db")));
+    }
+}
@@ -0,0 +1,188 @@
+//! Cross-validation of holiday data against the rule engine.
+//!
+//! Compares a date -> name map, as produced by `HolidayCache::parse_csv`, with
+//! `rules::holidays_in_year` for every year the data covers.
+
+use crate::cache::HolidayCache;
+use crate::rules;
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// A single difference between the data and the computed holidays
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Discrepancy {
+    /// A computed holiday that is absent from the data
+    Missing { date: NaiveDate, expected: String },
+    /// A holiday in the data that the rules do not produce
+    Extra { date: NaiveDate, name: String },
+    /// A holiday present in both with different names
+    Renamed {
+        date: NaiveDate,
+        name: String,
+        expected: String,
+    },
+}
+
+impl Discrepancy {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            Discrepancy::Missing { date, .. }
+            | Discrepancy::Extra { date, .. }
+            | Discrepancy::Renamed { date, .. } => *date,
+        }
+    }
+}
+
+/// Discrepancies found in a single year
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct YearReport {
+    pub year: i32,
+    pub data_count: usize,
+    pub computed_count: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Result of comparing holiday data with the rule engine, year by year
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    pub years: Vec<YearReport>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.years.iter().all(|year| year.discrepancies.is_empty())
+    }
+
+    pub fn discrepancies(&self) -> impl Iterator<Item = &Discrepancy> {
+        self.years.iter().flat_map(|year| year.discrepancies.iter())
+    }
+}
+
+/// Parse a Cabinet Office CSV and compare it with the rule engine
+pub fn verify_csv(csv_content: &str) -> Result<VerifyReport> {
+    let names = HolidayCache::parse_csv(csv_content)?;
+    Ok(verify_names(&names))
+}
+
+/// Compare a date -> name map with the rule engine for every year it covers
+pub fn verify_names(names: &HashMap<NaiveDate, String>) -> VerifyReport {
+    let mut by_year: BTreeMap<i32, BTreeMap<NaiveDate, &str>> = BTreeMap::new();
+    for (date, name) in names {
+        by_year
+            .entry(date.year())
+            .or_default()
+            .insert(*date, name.as_str());
+    }
+
+    let (first, last) = match (by_year.keys().next(), by_year.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return VerifyReport { years: Vec::new() },
+    };
+
+    let years = (first..=last)
+        .map(|year| {
+            let data = by_year.remove(&year).unwrap_or_default();
+            let computed: BTreeMap<NaiveDate, String> = rules::holidays_in_year(year)
+                .into_iter()
+                .map(|holiday| (holiday.date, holiday.name))
+                .collect();
+
+            let mut discrepancies = Vec::new();
+            for (date, expected) in &computed {
+                match data.get(date) {
+                    None => discrepancies.push(Discrepancy::Missing {
+                        date: *date,
+                        expected: expected.clone(),
+                    }),
+                    Some(name) if name != expected => discrepancies.push(Discrepancy::Renamed {
+                        date: *date,
+                        name: name.to_string(),
+                        expected: expected.clone(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+            for (date, name) in &data {
+                if !computed.contains_key(date) {
+                    discrepancies.push(Discrepancy::Extra {
+                        date: *date,
+                        name: name.to_string(),
+                    });
+                }
+            }
+            discrepancies.sort_by_key(Discrepancy::date);
+
+            YearReport {
+                year,
+                data_count: data.len(),
+                computed_count: computed.len(),
+                discrepancies,
+            }
+        })
+        .collect();
+
+    VerifyReport { years }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn csv_for_year(year: i32) -> String {
+        let mut csv = String::from("国民の祝日・休日月日,国民の祝日・休日名称\n");
+        for holiday in rules::holidays_in_year(year) {
+            csv.push_str(&format!(
+                "{},{}\n",
+                holiday.date.format("%Y/%-m/%-d"),
+                holiday.name
+            ));
+        }
+        csv
+    }
+
+    #[test]
+    fn test_verify_matching_csv() {
+        let report = verify_csv(&csv_for_year(2019)).unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.years.len(), 1);
+        assert_eq!(report.years[0].data_count, 22);
+    }
+
+    #[test]
+    fn test_verify_reports_discrepancies() {
+        let csv = csv_for_year(2023)
+            .replace("2023/1/9,成人の日\n", "")
+            .replace("文化の日", "文化ノ日")
+            + "2023/12/29,年末\n";
+
+        let report = verify_csv(&csv).unwrap();
+        let discrepancies: Vec<&Discrepancy> = report.discrepancies().collect();
+
+        assert!(!report.is_clean());
+        assert_eq!(
+            discrepancies,
+            vec![
+                &Discrepancy::Missing {
+                    date: date(2023, 1, 9),
+                    expected: "成人の日".to_string(),
+                },
+                &Discrepancy::Renamed {
+                    date: date(2023, 11, 3),
+                    name: "文化ノ日".to_string(),
+                    expected: "文化の日".to_string(),
+                },
+                &Discrepancy::Extra {
+                    date: date(2023, 12, 29),
+                    name: "年末".to_string(),
+                },
+            ]
+        );
+    }
+}
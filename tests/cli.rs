@@ -0,0 +1,75 @@
+//! Runs the `holidays_jp` binary end to end.
+#![cfg(feature = "fetch")]
+
+use assert_fs::prelude::*;
+use std::process::Command;
+
+/// The binary with a config whose `source_url` cannot be reached and a cache that does
+/// not exist, so any attempt to load the holiday data fails
+fn offline_command(dir: &assert_fs::TempDir) -> Command {
+    let config = dir.child("config.toml");
+    config
+        .write_str(&format!(
+            "[holiday_data]\nsource_url = \"http://127.0.0.1:9/syukujitsu.csv\"\ncache_file = {:?}\n",
+            dir.child("holidays.json").path()
+        ))
+        .unwrap();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_holidays_jp"));
+    command
+        .arg("--config")
+        .arg(config.path())
+        .env("HOME", dir.path())
+        .env("HOLIDAYS_JP_HOLIDAY_DATA_HTTP_RETRIES", "0")
+        .env_remove("HOLIDAYS_JP_CONFIG");
+    command
+}
+
+#[test]
+fn test_verify_file_works_offline() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let csv = concat!(env!("CARGO_MANIFEST_DIR"), "/data/syukujitsu.csv");
+
+    let output = offline_command(&dir)
+        .args(["verify", "--file", csv, "-o", "json"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!report["years"].as_array().unwrap().is_empty());
+    assert_eq!(report["report"]["encoding"], "UTF-8");
+    assert!(!dir.child("holidays.json").path().exists());
+}
+
+#[test]
+fn test_verify_file_rejects_malformed_bytes() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    // Neither UTF-8 nor Shift_JIS: 0x81 needs a trail byte of 0x40 or more
+    let csv = dir.child("broken.csv");
+    csv.write_binary(b"\x93\xfa\x95\x74,\x8f\x6a\x93\xfa\n2024/1/1,\x81\x20\n")
+        .unwrap();
+
+    let output = offline_command(&dir)
+        .args(["verify", "--file"])
+        .arg(csv.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Shift_JIS"), "{}", stderr);
+}
+
+#[test]
+fn test_count_is_bounded() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    for count in ["0", "1001"] {
+        let output = offline_command(&dir)
+            .args(["next", "-n", count])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--count"), "{}", stderr);
+    }
+}